@@ -11,7 +11,7 @@ pub enum PollError {
     NotInitialized,
     #[error("Pda did not match")]
     PdaNotMatched,
-    #[error("Only 255 Polls Supported")]
+    #[error("Poll Count Overflow")]
     PollsOverflow,
     #[error("Poll Already Created")]
     PollAlreadyCreated,
@@ -19,6 +19,80 @@ pub enum PollError {
     PollMismatch,
     #[error("Already Voted")]
     AlreadyVoted,
+    #[error("Poll Not Migrated")]
+    PollNotMigrated,
+    #[error("Poll Already Migrated")]
+    PollAlreadyMigrated,
+    #[error("Invalid Options Count")]
+    InvalidOptionsCount,
+    #[error("Unauthorized")]
+    Unauthorized,
+    #[error("Invalid Voting Window")]
+    InvalidVotingWindow,
+    #[error("Poll Not Started")]
+    PollNotStarted,
+    #[error("Poll Closed")]
+    PollClosed,
+    #[error("Poll Not Finalized")]
+    PollNotFinalized,
+    #[error("Vote Mode Mismatch")]
+    VoteModeMismatch,
+    #[error("Too Many Selections")]
+    TooManySelections,
+    #[error("Invalid Ranking")]
+    InvalidRanking,
+    #[error("Tally Complete")]
+    TallyComplete,
+    #[error("Token Account Mismatch")]
+    TokenAccountMismatch,
+    #[error("No Voting Weight")]
+    NoVotingWeight,
+    #[error("Credit Budget Exceeded")]
+    CreditBudgetExceeded,
+    #[error("Not On Allowlist")]
+    NotOnAllowlist,
+    #[error("Nft Not In Collection")]
+    NftNotInCollection,
+    #[error("Delegation Not Allowed")]
+    DelegationNotAllowed,
+    #[error("Not In Reveal Window")]
+    NotInRevealWindow,
+    #[error("Commitment Mismatch")]
+    CommitmentMismatch,
+    #[error("Reveal Not Over")]
+    RevealNotOver,
+    #[error("Proposal Locked")]
+    ProposalLocked,
+    #[error("Proposal Not Passed")]
+    ProposalNotPassed,
+    #[error("Proposal Already Executed")]
+    ProposalAlreadyExecuted,
+    #[error("Proposal Timelocked")]
+    ProposalTimelocked,
+    #[error("Proposal Cancelled")]
+    ProposalCancelled,
+    #[error("Timelock Over")]
+    TimelockOver,
+    #[error("Invalid Spend")]
+    InvalidSpend,
+    #[error("Invalid Veto Authority")]
+    InvalidVetoAuthority,
+    #[error("Poll Vetoed")]
+    PollVetoed,
+    #[error("Tally Not Done")]
+    TallyNotDone,
+    #[error("Rent Payer Mismatch")]
+    RentPayerMismatch,
+    #[error("Voter Did Not Sign")]
+    VoterNotSigner,
+    #[error("Vote Not Initialized")]
+    VoteNotInitialized,
+    #[error("Governance Config Already Set")]
+    GovernanceConfigSet,
+    #[error("Governance Config Not Set")]
+    GovernanceConfigNotSet,
+    #[error("Governance Rules Not Met")]
+    GovernanceRulesNotMet,
 }
 
 impl From<PollError> for ProgramError {
@@ -1,38 +1,396 @@
 use crate::error::{PollError, VoterError};
+use crate::state::{ProposalInstruction, VoteMode};
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::msg;
 use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
 
 #[derive(BorshDeserialize, BorshSerialize, Debug, Clone)]
 pub enum PollInstruction {
-    ///
     /// 0, init poll
-    ///   create poll count, if it does not exist
+    ///   create creator's poll count, if it does not exist
     ///   get poll count
-    ///   create poll
+    ///   create poll, seeded by creator and poll count
     ///   create poll options
+    ///   optional voting window, start_ts and end_ts are unix timestamps,
+    ///   0 (or left out) means no bound
+    ///   optional allow_revote, 1 lets voters change their vote while the
+    ///   poll is open
+    ///   optional vote_mode, 0 single choice, 1 approval voting,
+    ///   2 ranked choice, 3 quadratic voting
+    ///   optional max_selections for approval polls, 0 means no limit
+    ///   optional vote_mint, votes are weighted by the voter's balance of
+    ///   this SPL token, left out (or default) for one vote per voter
+    ///   optional lock_tokens, 1 moves voted tokens into an escrow until
+    ///   voting is over, needs a vote_mint
+    ///   optional credits, voice credits of every voter, needed by
+    ///   quadratic polls
+    ///   optional allowlist_root, merkle root of the voters allowed to vote,
+    ///   left out (or all 0) lets anyone vote
+    ///   optional nft_collection, only holders of a verified nft of this
+    ///   collection can vote, each nft once, can not be combined with a
+    ///   vote_mint
+    ///   optional reveal_end_ts, single choice polls with an end_ts only,
+    ///   ballots are committed until end_ts and revealed until reveal_end_ts,
+    ///   0 (or left out) for plain ballots
+    ///   optional quorum, votes (by weight) that have to be cast for the
+    ///   poll to be decided, 0 (or left out) for none
+    ///   optional threshold, basis points of the votes cast pass_option has
+    ///   to exceed, 5000 for a simple majority, 6666 for two thirds
+    ///   optional pass_option, option id the threshold applies to, 0 (or
+    ///   left out) for the winner
+    ///   optional timelock, seconds a passed executable poll waits before
+    ///   its instructions can be executed, measured on the clock's unix
+    ///   timestamp like the voting window (there is no slot based timelock)
+    ///   optional council, authority that can cancel queued proposals
+    ///   besides the creator, left out (or default) for none
+    ///   optional spend_recipient, spend_mint (default for lamports) and
+    ///   spend_amount, a spend poll pays amount out of the creator's
+    ///   treasury to the recipient once it passed, 0 (or left out) for none,
+    ///   its quorum, threshold and the voting window left have to meet the
+    ///   minimums of the creator's governance config
+    ///   optional veto_threshold, veto_keys_count (up to 5) and veto_keys,
+    ///   veto_threshold of the keys can veto the poll once it passed, 0 (or
+    ///   left out) for no veto authority
+    ///   optional allow_abstain and allow_nota, enable the built-in abstain
+    ///   and none of the above choices, not on ranked choice polls
+    ///
     ///  accounts
-    ///  -poll num account
-    ///  -poll account
-    ///  -system account
-    ///  -payer account
+    ///  - poll num account (seeded by creator)
+    ///  - poll account
+    ///  - system account
+    ///  - payer account, the poll creator (signer)
+    ///  - governance config pda account of the creator, spend polls only
+    ///  - ballot box account, ranked choice polls only
+    CreatePoll(Box<CreatePollArgs>),
     /// 1, vote poll
+    ///   user votes in poll, id is the poll index in its creator's namespace
+    ///   option_ids is a single option for single choice polls, on ranked
+    ///   choice polls it is the ballot, options by preference
+    ///   voting again changes the vote if the poll allows revotes
+    ///   allowlisted polls need the voter's merkle proof, it follows the
+    ///   option ids after a 0 byte, 32 bytes per node
+    ///   on commit-reveal polls a 0 byte and the commitment, the sha256 of the
+    ///   option id and a 32 byte salt, take the place of the option ids, the
+    ///   proof follows the commitment
+    ///   on polls enabling them, the option id 255 abstains and 254 votes
+    ///   none of the above, on any but ranked choice polls
+    ///
+    ///  accounts
     ///  - poll pda account
     ///  - voter pda account
-    ///  - voter fee payer account
+    ///  - voter fee payer account (signer)
     ///  - system account
-    ///   user votes in poll
-    CreatePoll {
-        title_length: u8,
-        title: String,
-        options_count: u8,
-        options_size: Vec<u8>,
-        options: Vec<String>,
-    },
+    ///  - ballot box account, ranked choice polls only
+    ///  - voter token account of the vote mint, token weighted polls only
+    ///  - vote mint, escrow account, token program and rent sysvar,
+    ///    polls locking tokens only, the whole token account is escrowed
+    ///  - voter nft token account and its metadata account, nft gated
+    ///    polls only, the voter pda is seeded by the nft mint
+    ///  - for every voter that delegated to the voter: the delegation
+    ///    account, the delegation for the poll when the first one covers
+    ///    every poll (it must not exist), the delegator's voter pda and on
+    ///    token weighted polls the delegator's token account
     VotePoll {
-        id: u8,
+        id: u64,
+        option_ids: Vec<u8>,
+        commitment: Option<[u8; 32]>,
+        proof: Vec<[u8; 32]>,
+    },
+    /// 2, migrate poll
+    ///   rewrite a poll created with a u8 id into the current layout,
+    ///   the poll keeps its global address and votes, it has no creator
+    ///
+    ///  accounts
+    ///  - poll pda account
+    ///  - system account
+    ///  - payer account
+    MigratePoll { id: u8 },
+    /// 3, close poll
+    ///   creator ends voting, the winning option (0 for a tie) is stored
+    ///   on the poll and no more votes are accepted
+    ///
+    ///  accounts
+    ///  - poll pda account
+    ///  - creator account (signer)
+    ClosePoll { id: u64 },
+    /// 4, close voter account
+    ///   voter deletes their voter pda once the poll is finalized
+    ///   (or deleted), its rent goes back to whoever paid it
+    ///
+    ///  accounts
+    ///  - poll pda account
+    ///  - voter pda account
+    ///  - voter account (signer)
+    ///  - rent payer account, the payer stored in the voter pda (the voter
+    ///    or the delegate that voted for them), receives the rent
+    ///  - creator account of the poll
+    ///  - voter nft token account, nft gated polls only
+    CloseVoterAccount { id: u64 },
+    /// 5, close poll account
+    ///   creator deletes a finalized poll and gets its rent back,
+    ///   the tally is logged first
+    ///
+    ///  accounts
+    ///  - poll pda account
+    ///  - creator account (signer), receives the rent
+    ///  - ballot box account, ranked choice polls only
+    ///  - proposal account, executable polls only
+    ClosePollAccount { id: u64 },
+    /// 6, retract vote
+    ///   voter withdraws their vote while the poll is open, the tally is
+    ///   decremented and the voter pda is closed, its rent goes back to
+    ///   whoever paid it, the voter can vote again
+    ///
+    ///  accounts
+    ///  - poll pda account
+    ///  - voter pda account
+    ///  - voter account (signer)
+    ///  - rent payer account, the payer stored in the voter pda (the voter
+    ///    or the delegate that voted for them), receives the rent
+    ///  - ballot box account, ranked choice polls only
+    ///  - voter nft token account, nft gated polls only
+    RetractVote { id: u64 },
+    /// 7, approval vote poll
+    ///   user votes for every option in selections on an approval poll,
+    ///   bit 0 of the bitmask is option 1, up to the poll's max selections
+    ///   voting again replaces the selections if the poll allows revotes
+    ///   allowlisted polls need the voter's merkle proof after selections
+    ///
+    ///  accounts
+    ///  - poll pda account
+    ///  - voter pda account
+    ///  - voter fee payer account (signer)
+    ///  - system account
+    ///  - voter token account of the vote mint, token weighted polls only
+    ///  - vote mint, escrow account, token program and rent sysvar,
+    ///    polls locking tokens only, the whole token account is escrowed
+    ///  - voter nft token account and its metadata account, nft gated
+    ///    polls only, the voter pda is seeded by the nft mint
+    ///  - for every voter that delegated to the voter: the delegation
+    ///    account, the delegation for the poll when the first one covers
+    ///    every poll (it must not exist), the delegator's voter pda and on
+    ///    token weighted polls the delegator's token account
+    ApprovalVotePoll {
+        id: u64,
+        selections: u64,
+        proof: Vec<[u8; 32]>,
+    },
+    /// 8, tally round
+    ///   count the next batch of ranked ballots once voting is over, anyone
+    ///   can call it until the instant runoff is decided, the winner (0 for
+    ///   a tie) is then stored on the poll and the poll is finalized
+    ///
+    ///  accounts
+    ///  - poll pda account
+    ///  - ballot box account
+    TallyRound { id: u64 },
+    /// 9, withdraw
+    ///   voter gets their escrowed tokens back once voting is over (the poll
+    ///   is finalized or its window ended) or the poll was deleted, the
+    ///   escrow is closed and its rent goes to the voter
+    ///
+    ///  accounts
+    ///  - poll pda account
+    ///  - escrow account
+    ///  - token account receiving the tokens
+    ///  - voter account (signer), receives the rent
+    ///  - creator account of the poll
+    ///  - token program
+    Withdraw { id: u64 },
+    /// 10, quadratic vote poll
+    ///   user spreads their voice credits over the options of a quadratic
+    ///   poll, votes has the votes for each option in order (a u8 count,
+    ///   then u16 each), k votes on an option cost k² credits, options left
+    ///   out get none
+    ///   voting again replaces the allocation if the poll allows revotes
+    ///   allowlisted polls need the voter's merkle proof after the votes
+    ///
+    ///  accounts
+    ///  - poll pda account
+    ///  - voter pda account
+    ///  - voter fee payer account (signer)
+    ///  - system account
+    ///  - voter token account of the vote mint, token weighted polls only
+    ///  - vote mint, escrow account, token program and rent sysvar,
+    ///    polls locking tokens only, the whole token account is escrowed
+    ///  - voter nft token account and its metadata account, nft gated
+    ///    polls only, the voter pda is seeded by the nft mint
+    ///  - for every voter that delegated to the voter: the delegation
+    ///    account, the delegation for the poll when the first one covers
+    ///    every poll (it must not exist), the delegator's voter pda and on
+    ///    token weighted polls the delegator's token account
+    QuadraticVotePoll {
+        id: u64,
+        votes: Vec<u16>,
+        proof: Vec<[u8; 32]>,
+    },
+    /// 11, delegate
+    ///   voter lets delegate vote for them on poll (the poll pda), left out
+    ///   (or default) on every poll, delegating again changes the delegate
+    ///   a delegation for a poll takes precedence over one for every poll
+    ///
+    ///  accounts
+    ///  - delegation pda account (seeded by delegator and poll)
+    ///  - delegator account (signer), pays for the delegation
+    ///  - delegate account
+    ///  - system account
+    Delegate { poll: Pubkey },
+    /// 12, undelegate
+    ///   voter takes back a delegation, the delegation pda is closed and its
+    ///   rent goes back to the delegator
+    ///
+    ///  accounts
+    ///  - delegation pda account
+    ///  - delegator account (signer), receives the rent
+    Undelegate { poll: Pubkey },
+    /// 13, reveal vote
+    ///   voter reveals the option id and salt of their committed ballot
+    ///   between the poll's end_ts and reveal_end_ts, the vote is then
+    ///   counted with the weight it was committed with
+    ///
+    ///  accounts
+    ///  - poll pda account
+    ///  - voter pda account
+    ///  - voter account (signer)
+    ///  - voter nft token account, nft gated polls only
+    RevealVote {
+        id: u64,
         option_id: u8,
+        salt: [u8; 32],
     },
+    /// 14, add proposal instruction
+    ///   creator attaches an instruction (borsh encoded program id, account
+    ///   metas and data) to an executable poll, only before voting starts
+    ///   so voters know what they vote on, the poll needs a start_ts
+    ///   the proposal is queued when the poll passes
+    ///   the governance pda (seeded by "Governance" and the creator) signs
+    ///   it when the proposal is executed
+    ///   the poll's quorum, threshold and voting window have to meet the
+    ///   minimums of the creator's governance config
+    ///
+    ///  accounts
+    ///  - poll pda account (writable)
+    ///  - proposal pda account (seeded by the poll's seeds, bump and
+    ///    "Proposal"), created on the first instruction
+    ///  - creator account (signer), pays for the proposal
+    ///  - system account
+    ///  - governance config pda account of the creator
+    AddProposalInstruction {
+        id: u64,
+        instruction: ProposalInstruction,
+    },
+    /// 15, execute proposal
+    ///   anyone runs the instructions of a queued proposal once its
+    ///   timelock is over, in order, by cross program invocation signed by
+    ///   the governance pda
+    ///
+    ///  accounts
+    ///  - poll pda account
+    ///  - proposal pda account
+    ///  - governance pda account
+    ///  - the programs and accounts the instructions use
+    ExecuteProposal { id: u64 },
+    /// 16, cancel proposal
+    ///   creator or council aborts a queued proposal before its timelock is
+    ///   over, it can not be executed anymore
+    ///
+    ///  accounts
+    ///  - poll pda account
+    ///  - creator or council account (signer)
+    CancelProposal { id: u64 },
+    /// 17, fund treasury
+    ///   anyone deposits lamports or tokens into the treasury of a creator's
+    ///   polls, the treasury pda is seeded by "Treasury" and the creator
+    ///
+    ///  accounts
+    ///  - treasury pda account
+    ///  - funder account (signer)
+    ///  - system account
+    ///
+    ///  token deposits only (mint is not default):
+    ///  - funder token account
+    ///  - mint account
+    ///  - treasury token pda account (seeded by the treasury seeds and the
+    ///    mint), owned by the treasury pda, created on the first deposit
+    ///  - token program
+    ///  - rent sysvar
+    FundTreasury {
+        creator: Pubkey,
+        mint: Pubkey,
+        amount: u64,
+    },
+    /// 18, execute spend
+    ///   anyone pays out a queued spend once its timelock is over, the
+    ///   treasury pda signs the transfer
+    ///
+    ///  accounts
+    ///  - poll pda account
+    ///  - treasury pda account
+    ///  - recipient account, the recipient's token account of the spend mint
+    ///    for token spends
+    ///  - system account
+    ///
+    ///  token spends only:
+    ///  - treasury token pda account
+    ///  - token program
+    ExecuteSpend { id: u64 },
+    /// 19, veto poll
+    ///   veto_threshold of the poll's veto keys block a passed poll before
+    ///   its proposal or spend is executed, the poll ends vetoed
+    ///
+    ///  accounts
+    ///  - poll pda account
+    ///  - veto key accounts (signers)
+    VetoPoll { id: u64 },
+    /// 20, set governance config
+    ///   creator fixes the minimum quorum, threshold (basis points) and
+    ///   voting period (seconds) of their executable and spend polls, once,
+    ///   it can not be changed afterwards
+    ///   the config pda is seeded by "GovernanceConfig" and the creator
+    ///
+    ///  accounts
+    ///  - governance config pda account
+    ///  - creator account (signer), pays for the config
+    ///  - system account
+    SetGovernanceConfig {
+        min_quorum: u64,
+        min_threshold: u16,
+        min_voting_period: i64,
+    },
+}
+
+//data of PollInstruction::CreatePoll
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone)]
+pub struct CreatePollArgs {
+    pub title_length: u8,
+    pub title: String,
+    pub options_count: u8,
+    pub options_size: Vec<u8>,
+    pub options: Vec<String>,
+    pub start_ts: i64,
+    pub end_ts: i64,
+    pub allow_revote: bool,
+    pub vote_mode: VoteMode,
+    pub max_selections: u8,
+    pub vote_mint: Pubkey,
+    pub lock_tokens: bool,
+    pub credits: u64,
+    pub allowlist_root: [u8; 32],
+    pub nft_collection: Pubkey,
+    pub reveal_end_ts: i64,
+    pub quorum: u64,
+    pub threshold: u16,
+    pub pass_option: u8,
+    pub timelock: i64,
+    pub council: Pubkey,
+    pub spend_recipient: Pubkey,
+    pub spend_mint: Pubkey,
+    pub spend_amount: u64,
+    pub veto_threshold: u8,
+    pub veto_keys: Vec<Pubkey>,
+    pub allow_abstain: bool,
+    pub allow_nota: bool,
 }
 
 impl PollInstruction {
@@ -43,6 +401,29 @@ impl PollInstruction {
         match tag {
             0 => PollInstruction::create_poll(rest),
             1 => PollInstruction::vote_poll(rest),
+            2 => PollInstruction::migrate_poll(rest),
+            3 => PollInstruction::close_poll(rest),
+            4 => PollInstruction::close_voter_account(rest),
+            5 => PollInstruction::close_poll_account(rest),
+            6 => PollInstruction::retract_vote(rest),
+            7 => PollInstruction::approval_vote_poll(rest),
+            8 => PollInstruction::tally_round(rest),
+            9 => PollInstruction::withdraw(rest),
+            10 => PollInstruction::quadratic_vote_poll(rest),
+            11 => Ok(PollInstruction::Delegate {
+                poll: PollInstruction::unpack_delegation_poll(rest),
+            }),
+            12 => Ok(PollInstruction::Undelegate {
+                poll: PollInstruction::unpack_delegation_poll(rest),
+            }),
+            13 => PollInstruction::reveal_vote(rest),
+            14 => PollInstruction::add_proposal_instruction(rest),
+            15 => PollInstruction::execute_proposal(rest),
+            16 => PollInstruction::cancel_proposal(rest),
+            17 => PollInstruction::fund_treasury(rest),
+            18 => PollInstruction::execute_spend(rest),
+            19 => PollInstruction::veto_poll(rest),
+            20 => PollInstruction::set_governance_config(rest),
             _ => Err(ProgramError::InvalidInstructionData),
         }
     }
@@ -54,14 +435,13 @@ impl PollInstruction {
             .and_then(|slice| slice.try_into().ok())
             .map(u8::from_le_bytes)
             .ok_or(ProgramError::from(PollError::InvalidInstrucitonData))?;
-        start_index = start_index + 1;
+        start_index += 1;
         msg!("title_length={} start_index={}", title_length, start_index);
         let title = input
             .get(start_index..start_index + title_length as usize)
-            .and_then(|slice| slice.try_into().ok())
-            .map(String::from_utf8)
+            .and_then(|slice| String::from_utf8(slice.to_vec()).ok())
             .ok_or(ProgramError::from(PollError::InvalidInstrucitonData))?;
-        start_index = start_index + title_length as usize;
+        start_index += title_length as usize;
         msg!("title={:?} start_index={}", title, start_index);
 
         let options_count = input
@@ -69,7 +449,7 @@ impl PollInstruction {
             .and_then(|slice| slice.try_into().ok())
             .map(u8::from_le_bytes)
             .ok_or(ProgramError::from(PollError::InvalidInstrucitonData))?;
-        start_index = start_index + 1;
+        start_index += 1;
         msg!(
             "options_count={} start_index={}",
             options_count,
@@ -88,7 +468,7 @@ impl PollInstruction {
                     .map(u8::from_le_bytes)
                     .ok_or(ProgramError::from(PollError::InvalidInstrucitonData))?,
             );
-            start_index = start_index + 1;
+            start_index += 1;
         }
 
         msg!(
@@ -99,41 +479,478 @@ impl PollInstruction {
         for i in 0..options_count as usize {
             let opt = input
                 .get(start_index..start_index + *options_length.get(i).unwrap() as usize)
-                .and_then(|slice| slice.try_into().ok())
-                .map(String::from_utf8)
+                .and_then(|slice| String::from_utf8(slice.to_vec()).ok())
                 .ok_or(ProgramError::from(PollError::InvalidInstrucitonData))?;
-            options_str.push(opt.unwrap());
-            start_index = start_index + *options_length.get(i).unwrap() as usize;
+            options_str.push(opt);
+            start_index += *options_length.get(i).unwrap() as usize;
         }
 
         msg!("options_str={:?} start_index={}", options_str, start_index);
 
-        Ok(PollInstruction::CreatePoll {
+        let start_ts = input
+            .get(start_index..start_index + 8)
+            .and_then(|slice| slice.try_into().ok())
+            .map(i64::from_le_bytes)
+            .unwrap_or(0);
+        start_index += 8;
+
+        let end_ts = input
+            .get(start_index..start_index + 8)
+            .and_then(|slice| slice.try_into().ok())
+            .map(i64::from_le_bytes)
+            .unwrap_or(0);
+        start_index += 8;
+
+        msg!("start_ts={} end_ts={}", start_ts, end_ts);
+
+        let allow_revote = match input.get(start_index) {
+            None | Some(0) => false,
+            Some(1) => true,
+            _ => return Err(ProgramError::from(PollError::InvalidInstrucitonData)),
+        };
+        start_index += 1;
+
+        let vote_mode = match input.get(start_index) {
+            None | Some(0) => VoteMode::Single,
+            Some(1) => VoteMode::Approval,
+            Some(2) => VoteMode::Ranked,
+            Some(3) => VoteMode::Quadratic,
+            _ => return Err(ProgramError::from(PollError::InvalidInstrucitonData)),
+        };
+        start_index += 1;
+
+        let max_selections = input.get(start_index).copied().unwrap_or(0);
+        start_index += 1;
+
+        let vote_mint = input
+            .get(start_index..start_index + 32)
+            .map(Pubkey::new)
+            .unwrap_or_default();
+        start_index += 32;
+
+        let lock_tokens = match input.get(start_index) {
+            None | Some(0) => false,
+            Some(1) => true,
+            _ => return Err(ProgramError::from(PollError::InvalidInstrucitonData)),
+        };
+        start_index += 1;
+
+        let credits = input
+            .get(start_index..start_index + 8)
+            .and_then(|slice| slice.try_into().ok())
+            .map(u64::from_le_bytes)
+            .unwrap_or(0);
+        start_index += 8;
+
+        let allowlist_root = input
+            .get(start_index..start_index + 32)
+            .and_then(|slice| slice.try_into().ok())
+            .unwrap_or([0; 32]);
+        start_index += 32;
+
+        let nft_collection = input
+            .get(start_index..start_index + 32)
+            .map(Pubkey::new)
+            .unwrap_or_default();
+        start_index += 32;
+
+        let reveal_end_ts = input
+            .get(start_index..start_index + 8)
+            .and_then(|slice| slice.try_into().ok())
+            .map(i64::from_le_bytes)
+            .unwrap_or(0);
+        start_index += 8;
+
+        let quorum = input
+            .get(start_index..start_index + 8)
+            .and_then(|slice| slice.try_into().ok())
+            .map(u64::from_le_bytes)
+            .unwrap_or(0);
+        start_index += 8;
+
+        let threshold = input
+            .get(start_index..start_index + 2)
+            .and_then(|slice| slice.try_into().ok())
+            .map(u16::from_le_bytes)
+            .unwrap_or(0);
+        start_index += 2;
+
+        let pass_option = input.get(start_index).copied().unwrap_or(0);
+        start_index += 1;
+
+        let timelock = input
+            .get(start_index..start_index + 8)
+            .and_then(|slice| slice.try_into().ok())
+            .map(i64::from_le_bytes)
+            .unwrap_or(0);
+        start_index += 8;
+
+        let council = input
+            .get(start_index..start_index + 32)
+            .map(Pubkey::new)
+            .unwrap_or_default();
+        start_index += 32;
+
+        let spend_recipient = input
+            .get(start_index..start_index + 32)
+            .map(Pubkey::new)
+            .unwrap_or_default();
+        start_index += 32;
+
+        let spend_mint = input
+            .get(start_index..start_index + 32)
+            .map(Pubkey::new)
+            .unwrap_or_default();
+        start_index += 32;
+
+        let spend_amount = input
+            .get(start_index..start_index + 8)
+            .and_then(|slice| slice.try_into().ok())
+            .map(u64::from_le_bytes)
+            .unwrap_or(0);
+        start_index += 8;
+
+        let veto_threshold = input.get(start_index).copied().unwrap_or(0);
+        start_index += 1;
+
+        let veto_keys_count = input.get(start_index).copied().unwrap_or(0) as usize;
+        start_index += 1;
+
+        let veto_keys = input
+            .get(start_index..start_index + veto_keys_count * 32)
+            .ok_or(ProgramError::from(PollError::InvalidInstrucitonData))?
+            .chunks_exact(32)
+            .map(Pubkey::new)
+            .collect();
+        start_index += veto_keys_count * 32;
+
+        let allow_abstain = match input.get(start_index) {
+            None | Some(0) => false,
+            Some(1) => true,
+            _ => return Err(ProgramError::from(PollError::InvalidInstrucitonData)),
+        };
+        start_index += 1;
+
+        let allow_nota = match input.get(start_index) {
+            None | Some(0) => false,
+            Some(1) => true,
+            _ => return Err(ProgramError::from(PollError::InvalidInstrucitonData)),
+        };
+
+        Ok(PollInstruction::CreatePoll(Box::new(CreatePollArgs {
             title_length,
-            title: title.unwrap(),
+            title,
             options_count,
             options_size: options_length,
             options: options_str,
-        })
+            start_ts,
+            end_ts,
+            allow_revote,
+            vote_mode,
+            max_selections,
+            vote_mint,
+            lock_tokens,
+            credits,
+            allowlist_root,
+            nft_collection,
+            reveal_end_ts,
+            quorum,
+            threshold,
+            pass_option,
+            timelock,
+            council,
+            spend_recipient,
+            spend_mint,
+            spend_amount,
+            veto_threshold,
+            veto_keys,
+            allow_abstain,
+            allow_nota,
+        })))
     }
 
     fn vote_poll(input: &[u8]) -> Result<Self, ProgramError> {
+        let poll_id = input
+            .get(0..8)
+            .and_then(|split| split.try_into().ok())
+            .map(u64::from_le_bytes)
+            .ok_or(ProgramError::from(PollError::InvalidInstrucitonData))?;
+
+        //option ids are never 0, a 0 byte starts the proof
+        let rest = input.get(8..).unwrap_or_default();
+        let (option_ids, proof) = match rest.iter().position(|byte| *byte == 0) {
+            Some(end) => (&rest[..end], &rest[end + 1..]),
+            None => (rest, &[][..]),
+        };
+
+        //no option ids, a commitment comes first
+        if option_ids.is_empty() {
+            let commitment = proof
+                .get(0..32)
+                .and_then(|split| split.try_into().ok())
+                .filter(|commitment| *commitment != [0; 32])
+                .ok_or(ProgramError::from(PollError::InvalidInstrucitonData))?;
+            return Ok(PollInstruction::VotePoll {
+                id: poll_id,
+                option_ids: Vec::new(),
+                commitment: Some(commitment),
+                proof: PollInstruction::unpack_proof(&proof[32..])?,
+            });
+        }
+
+        Ok(PollInstruction::VotePoll {
+            id: poll_id,
+            option_ids: option_ids.to_vec(),
+            commitment: None,
+            proof: PollInstruction::unpack_proof(proof)?,
+        })
+    }
+
+    fn migrate_poll(input: &[u8]) -> Result<Self, ProgramError> {
         let poll_id = input
             .get(0..1)
             .and_then(|split| split.try_into().ok())
             .map(u8::from_le_bytes)
             .ok_or(ProgramError::from(PollError::InvalidInstrucitonData))?;
+        Ok(PollInstruction::MigratePoll { id: poll_id })
+    }
+
+    fn close_poll(input: &[u8]) -> Result<Self, ProgramError> {
+        let poll_id = input
+            .get(0..8)
+            .and_then(|split| split.try_into().ok())
+            .map(u64::from_le_bytes)
+            .ok_or(ProgramError::from(PollError::InvalidInstrucitonData))?;
+        Ok(PollInstruction::ClosePoll { id: poll_id })
+    }
+
+    fn close_voter_account(input: &[u8]) -> Result<Self, ProgramError> {
+        let poll_id = input
+            .get(0..8)
+            .and_then(|split| split.try_into().ok())
+            .map(u64::from_le_bytes)
+            .ok_or(ProgramError::from(PollError::InvalidInstrucitonData))?;
+        Ok(PollInstruction::CloseVoterAccount { id: poll_id })
+    }
+
+    fn close_poll_account(input: &[u8]) -> Result<Self, ProgramError> {
+        let poll_id = input
+            .get(0..8)
+            .and_then(|split| split.try_into().ok())
+            .map(u64::from_le_bytes)
+            .ok_or(ProgramError::from(PollError::InvalidInstrucitonData))?;
+        Ok(PollInstruction::ClosePollAccount { id: poll_id })
+    }
+
+    fn retract_vote(input: &[u8]) -> Result<Self, ProgramError> {
+        let poll_id = input
+            .get(0..8)
+            .and_then(|split| split.try_into().ok())
+            .map(u64::from_le_bytes)
+            .ok_or(ProgramError::from(PollError::InvalidInstrucitonData))?;
+        Ok(PollInstruction::RetractVote { id: poll_id })
+    }
+
+    fn approval_vote_poll(input: &[u8]) -> Result<Self, ProgramError> {
+        let poll_id = input
+            .get(0..8)
+            .and_then(|split| split.try_into().ok())
+            .map(u64::from_le_bytes)
+            .ok_or(ProgramError::from(PollError::InvalidInstrucitonData))?;
+
+        let selections = input
+            .get(8..16)
+            .and_then(|split| split.try_into().ok())
+            .map(u64::from_le_bytes)
+            .ok_or(ProgramError::from(PollError::InvalidInstrucitonData))?;
+        Ok(PollInstruction::ApprovalVotePoll {
+            id: poll_id,
+            selections,
+            proof: PollInstruction::unpack_proof(&input[16..])?,
+        })
+    }
+
+    fn tally_round(input: &[u8]) -> Result<Self, ProgramError> {
+        let poll_id = input
+            .get(0..8)
+            .and_then(|split| split.try_into().ok())
+            .map(u64::from_le_bytes)
+            .ok_or(ProgramError::from(PollError::InvalidInstrucitonData))?;
+        Ok(PollInstruction::TallyRound { id: poll_id })
+    }
+
+    fn withdraw(input: &[u8]) -> Result<Self, ProgramError> {
+        let poll_id = input
+            .get(0..8)
+            .and_then(|split| split.try_into().ok())
+            .map(u64::from_le_bytes)
+            .ok_or(ProgramError::from(PollError::InvalidInstrucitonData))?;
+        Ok(PollInstruction::Withdraw { id: poll_id })
+    }
+
+    fn quadratic_vote_poll(input: &[u8]) -> Result<Self, ProgramError> {
+        let poll_id = input
+            .get(0..8)
+            .and_then(|split| split.try_into().ok())
+            .map(u64::from_le_bytes)
+            .ok_or(ProgramError::from(PollError::InvalidInstrucitonData))?;
+
+        let votes_count = input
+            .get(8)
+            .copied()
+            .filter(|count| *count > 0)
+            .ok_or(ProgramError::from(PollError::InvalidInstrucitonData))?;
+        let proof_index = 9 + 2 * votes_count as usize;
+
+        let votes = input
+            .get(9..proof_index)
+            .map(|split| {
+                split
+                    .chunks_exact(2)
+                    .map(|votes| u16::from_le_bytes([votes[0], votes[1]]))
+                    .collect()
+            })
+            .ok_or(ProgramError::from(PollError::InvalidInstrucitonData))?;
+        Ok(PollInstruction::QuadraticVotePoll {
+            id: poll_id,
+            votes,
+            proof: PollInstruction::unpack_proof(&input[proof_index..])?,
+        })
+    }
+
+    fn reveal_vote(input: &[u8]) -> Result<Self, ProgramError> {
+        let poll_id = input
+            .get(0..8)
+            .and_then(|split| split.try_into().ok())
+            .map(u64::from_le_bytes)
+            .ok_or(ProgramError::from(PollError::InvalidInstrucitonData))?;
 
         let option_id = input
-            .get(1..2)
+            .get(8)
+            .copied()
+            .ok_or(ProgramError::from(PollError::InvalidInstrucitonData))?;
+
+        let salt = input
+            .get(9..41)
             .and_then(|split| split.try_into().ok())
-            .map(u8::from_le_bytes)
             .ok_or(ProgramError::from(PollError::InvalidInstrucitonData))?;
-        Ok(PollInstruction::VotePoll {
+        Ok(PollInstruction::RevealVote {
             id: poll_id,
             option_id,
+            salt,
+        })
+    }
+
+    fn add_proposal_instruction(input: &[u8]) -> Result<Self, ProgramError> {
+        let poll_id = input
+            .get(0..8)
+            .and_then(|split| split.try_into().ok())
+            .map(u64::from_le_bytes)
+            .ok_or(ProgramError::from(PollError::InvalidInstrucitonData))?;
+
+        let instruction = input
+            .get(8..)
+            .and_then(|split| ProposalInstruction::try_from_slice(split).ok())
+            .ok_or(ProgramError::from(PollError::InvalidInstrucitonData))?;
+        Ok(PollInstruction::AddProposalInstruction {
+            id: poll_id,
+            instruction,
+        })
+    }
+
+    fn execute_proposal(input: &[u8]) -> Result<Self, ProgramError> {
+        let poll_id = input
+            .get(0..8)
+            .and_then(|split| split.try_into().ok())
+            .map(u64::from_le_bytes)
+            .ok_or(ProgramError::from(PollError::InvalidInstrucitonData))?;
+        Ok(PollInstruction::ExecuteProposal { id: poll_id })
+    }
+
+    fn cancel_proposal(input: &[u8]) -> Result<Self, ProgramError> {
+        let poll_id = input
+            .get(0..8)
+            .and_then(|split| split.try_into().ok())
+            .map(u64::from_le_bytes)
+            .ok_or(ProgramError::from(PollError::InvalidInstrucitonData))?;
+        Ok(PollInstruction::CancelProposal { id: poll_id })
+    }
+
+    fn fund_treasury(input: &[u8]) -> Result<Self, ProgramError> {
+        let creator = input
+            .get(0..32)
+            .map(Pubkey::new)
+            .ok_or(ProgramError::from(PollError::InvalidInstrucitonData))?;
+        let mint = input
+            .get(32..64)
+            .map(Pubkey::new)
+            .ok_or(ProgramError::from(PollError::InvalidInstrucitonData))?;
+        let amount = input
+            .get(64..72)
+            .and_then(|split| split.try_into().ok())
+            .map(u64::from_le_bytes)
+            .ok_or(ProgramError::from(PollError::InvalidInstrucitonData))?;
+        Ok(PollInstruction::FundTreasury {
+            creator,
+            mint,
+            amount,
+        })
+    }
+
+    fn execute_spend(input: &[u8]) -> Result<Self, ProgramError> {
+        let poll_id = input
+            .get(0..8)
+            .and_then(|split| split.try_into().ok())
+            .map(u64::from_le_bytes)
+            .ok_or(ProgramError::from(PollError::InvalidInstrucitonData))?;
+        Ok(PollInstruction::ExecuteSpend { id: poll_id })
+    }
+
+    fn veto_poll(input: &[u8]) -> Result<Self, ProgramError> {
+        let poll_id = input
+            .get(0..8)
+            .and_then(|split| split.try_into().ok())
+            .map(u64::from_le_bytes)
+            .ok_or(ProgramError::from(PollError::InvalidInstrucitonData))?;
+        Ok(PollInstruction::VetoPoll { id: poll_id })
+    }
+
+    fn set_governance_config(input: &[u8]) -> Result<Self, ProgramError> {
+        let min_quorum = input
+            .get(0..8)
+            .and_then(|split| split.try_into().ok())
+            .map(u64::from_le_bytes)
+            .ok_or(ProgramError::from(PollError::InvalidInstrucitonData))?;
+        let min_threshold = input
+            .get(8..10)
+            .and_then(|split| split.try_into().ok())
+            .map(u16::from_le_bytes)
+            .ok_or(ProgramError::from(PollError::InvalidInstrucitonData))?;
+        let min_voting_period = input
+            .get(10..18)
+            .and_then(|split| split.try_into().ok())
+            .map(i64::from_le_bytes)
+            .ok_or(ProgramError::from(PollError::InvalidInstrucitonData))?;
+        Ok(PollInstruction::SetGovernanceConfig {
+            min_quorum,
+            min_threshold,
+            min_voting_period,
         })
     }
+
+    //poll a delegation is for, default for every poll
+    fn unpack_delegation_poll(input: &[u8]) -> Pubkey {
+        input.get(0..32).map(Pubkey::new).unwrap_or_default()
+    }
+
+    //merkle proof nodes of an allowlisted voter, 32 bytes each
+    fn unpack_proof(input: &[u8]) -> Result<Vec<[u8; 32]>, ProgramError> {
+        let nodes = input.chunks_exact(32);
+        if !nodes.remainder().is_empty() {
+            return Err(ProgramError::from(PollError::InvalidInstrucitonData));
+        }
+        Ok(nodes.map(|node| node.try_into().unwrap()).collect())
+    }
 }
 
 #[derive(BorshDeserialize, BorshSerialize, Debug, Clone)]
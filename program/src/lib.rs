@@ -1,4 +1,5 @@
 pub mod entrypoint;
 pub mod error;
 pub mod instruction;
-pub mod processor;pub mod state;
+pub mod processor;
+pub mod state;
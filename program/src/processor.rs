@@ -1,19 +1,120 @@
 use crate::error::PollError;
-use crate::instruction::PollInstruction;
-use crate::state::{Poll, PollCount, PollOption, PollVoter, POLL_OPTION_SIZE, POLL_TITLE_SIZE};
+use crate::instruction::{CreatePollArgs, PollInstruction};
+use crate::state::{
+    pad_text, token_metadata, BallotBox, Delegation, GovernanceConfig, NftMetadata, Poll,
+    PollCount, PollOption, PollOutcome, PollStatus, PollVoter, Proposal, ProposalInstruction,
+    ProposalState, VoteMode, POLL_OPTION_SIZE, POLL_TITLE_SIZE, POLL_VETO_KEYS,
+};
 use solana_program::account_info::{next_account_info, AccountInfo};
 use solana_program::entrypoint::ProgramResult;
+use solana_program::hash::hashv;
 use solana_program::msg;
-use solana_program::program::invoke_signed;
+use solana_program::program::{invoke, invoke_signed};
 use solana_program::program_error::ProgramError;
 use solana_program::program_pack::{IsInitialized, Pack};
 use solana_program::pubkey::Pubkey;
 use solana_program::system_instruction;
-use solana_program::sysvar::{rent::Rent, Sysvar};
+use solana_program::sysvar::{clock::Clock, rent::Rent, Sysvar};
+use spl_token::state::Account as TokenAccount;
+use std::slice::Iter;
 use std::str;
 
 const POLL_COUNT_SEED: &[u8; 9] = b"PollCount";
 const POLL_SEED: &[u8; 4] = b"Poll";
+const BALLOTS_SEED: &[u8; 7] = b"Ballots";
+const ESCROW_SEED: &[u8; 6] = b"Escrow";
+const DELEGATION_SEED: &[u8; 10] = b"Delegation";
+const PROPOSAL_SEED: &[u8; 8] = b"Proposal";
+const GOVERNANCE_SEED: &[u8; 10] = b"Governance";
+const TREASURY_SEED: &[u8; 8] = b"Treasury";
+const GOVERNANCE_CONFIG_SEED: &[u8; 16] = b"GovernanceConfig";
+
+//polls up to 255 were created with a single byte id seed,
+//keep deriving them that way so they stay at the same address
+pub fn poll_id_seed(id: u64) -> Vec<u8> {
+    match u8::try_from(id) {
+        Ok(id) => vec![id],
+        Err(_) => id.to_le_bytes().to_vec(),
+    }
+}
+
+//seeds of a poll pda (without bump)
+//polls are seeded by creator and index, polls from the old global
+//namespace have no creator and keep the global seeds
+pub fn poll_seeds(creator: &Pubkey, id: u64) -> Vec<Vec<u8>> {
+    if *creator == Pubkey::default() {
+        vec![POLL_SEED.to_vec(), poll_id_seed(id)]
+    } else {
+        vec![
+            POLL_SEED.to_vec(),
+            creator.to_bytes().to_vec(),
+            id.to_le_bytes().to_vec(),
+        ]
+    }
+}
+
+//seeds of a ranked choice poll's ballot box (without bump),
+//poll seeds have to include the poll's bump
+pub fn ballot_box_seeds(poll_seeds: &[Vec<u8>]) -> Vec<Vec<u8>> {
+    let mut seeds = poll_seeds.to_vec();
+    seeds.push(BALLOTS_SEED.to_vec());
+    seeds
+}
+
+//seeds of a voter's token escrow (without bump), voter seeds are the
+//poll seeds with bump followed by the voter
+pub fn escrow_seeds(voter_seeds: &[Vec<u8>]) -> Vec<Vec<u8>> {
+    let mut seeds = voter_seeds.to_vec();
+    seeds.push(ESCROW_SEED.to_vec());
+    seeds
+}
+
+//seeds of a delegation pda (without bump), poll is the poll pda,
+//default for a delegation on every poll
+pub fn delegation_seeds(delegator: &Pubkey, poll: &Pubkey) -> Vec<Vec<u8>> {
+    vec![
+        DELEGATION_SEED.to_vec(),
+        delegator.to_bytes().to_vec(),
+        poll.to_bytes().to_vec(),
+    ]
+}
+
+//seeds of an executable poll's proposal (without bump),
+//poll seeds have to include the poll's bump
+pub fn proposal_seeds(poll_seeds: &[Vec<u8>]) -> Vec<Vec<u8>> {
+    let mut seeds = poll_seeds.to_vec();
+    seeds.push(PROPOSAL_SEED.to_vec());
+    seeds
+}
+
+//seeds of the governance pda (without bump) signing the proposals of a creator
+pub fn governance_seeds(creator: &Pubkey) -> Vec<Vec<u8>> {
+    vec![GOVERNANCE_SEED.to_vec(), creator.to_bytes().to_vec()]
+}
+
+//seeds of the governance config pda (without bump), the minimums every
+//executable and spend poll of a creator has to meet
+pub fn governance_config_seeds(creator: &Pubkey) -> Vec<Vec<u8>> {
+    vec![GOVERNANCE_CONFIG_SEED.to_vec(), creator.to_bytes().to_vec()]
+}
+
+//seeds of the treasury pda (without bump) holding the lamports spend polls
+//of a creator pay out
+pub fn treasury_seeds(creator: &Pubkey) -> Vec<Vec<u8>> {
+    vec![TREASURY_SEED.to_vec(), creator.to_bytes().to_vec()]
+}
+
+//seeds of the treasury's token account of a mint (without bump),
+//the treasury pda is its authority
+pub fn treasury_token_seeds(creator: &Pubkey, mint: &Pubkey) -> Vec<Vec<u8>> {
+    let mut seeds = treasury_seeds(creator);
+    seeds.push(mint.to_bytes().to_vec());
+    seeds
+}
+
+pub fn seed_refs(seeds: &[Vec<u8>]) -> Vec<&[u8]> {
+    seeds.iter().map(|seed| seed.as_slice()).collect()
+}
 
 pub fn assert_true(cond: bool, err: ProgramError, msg: &str) -> ProgramResult {
     if !cond {
@@ -23,6 +124,201 @@ pub fn assert_true(cond: bool, err: ProgramError, msg: &str) -> ProgramResult {
         Ok(())
     }
 }
+
+//administrative instructions on a poll have to be signed by its creator
+pub fn assert_creator(poll: &Poll, authority: &AccountInfo) -> ProgramResult {
+    assert_true(
+        authority.is_signer && poll.creator == *authority.key,
+        ProgramError::from(PollError::Unauthorized),
+        "Only the poll creator can do this",
+    )
+}
+//executable and spend polls can not undercut the creator's config
+pub fn assert_governance_rules(
+    config: &GovernanceConfig,
+    quorum: u64,
+    threshold: u16,
+    voting_period: i64,
+) -> ProgramResult {
+    assert_true(
+        quorum >= config.min_quorum
+            && threshold >= config.min_threshold
+            && voting_period >= config.min_voting_period,
+        ProgramError::from(PollError::GovernanceRulesNotMet),
+        "Quorum, threshold or voting period is below the governance config",
+    )
+}
+
+//votes are only accepted (or retracted) while the poll is open
+pub fn assert_poll_open(poll: &Poll) -> ProgramResult {
+    assert_true(
+        poll.status == PollStatus::Open,
+        ProgramError::from(PollError::PollClosed),
+        "Poll is closed",
+    )?;
+
+    let now = Clock::get()?.unix_timestamp;
+    assert_true(
+        poll.start_ts == 0 || now >= poll.start_ts,
+        ProgramError::from(PollError::PollNotStarted),
+        "Voting has not started yet",
+    )?;
+    assert_true(
+        poll.end_ts == 0 || now < poll.end_ts,
+        ProgramError::from(PollError::PollClosed),
+        "Voting has ended",
+    )
+}
+
+//voting is over once the poll is finalized or its voting window has ended
+pub fn assert_voting_over(poll: &Poll) -> ProgramResult {
+    let ended = poll.end_ts != 0 && Clock::get()?.unix_timestamp >= poll.end_ts;
+    assert_true(
+        poll.status == PollStatus::Finalized || ended,
+        ProgramError::from(PollError::PollNotFinalized),
+        "Voting is not over yet",
+    )
+}
+
+//committed ballots are revealed after voting ends, until the reveal end
+pub fn assert_reveal_open(poll: &Poll) -> ProgramResult {
+    let now = Clock::get()?.unix_timestamp;
+    assert_true(
+        poll.status == PollStatus::Open
+            && poll.reveal_end_ts != 0
+            && now >= poll.end_ts
+            && now < poll.reveal_end_ts,
+        ProgramError::from(PollError::NotInRevealWindow),
+        "Ballots can not be revealed now",
+    )
+}
+
+//only queued proposals can be executed or cancelled
+pub fn assert_proposal_queued(poll: &Poll) -> ProgramResult {
+    match poll.proposal_state {
+        ProposalState::Queued => Ok(()),
+        ProposalState::Executed => {
+            msg!("Proposal was already executed");
+            Err(ProgramError::from(PollError::ProposalAlreadyExecuted))
+        }
+        ProposalState::Cancelled if poll.outcome == PollOutcome::Vetoed => {
+            msg!("Poll was vetoed");
+            Err(ProgramError::from(PollError::PollVetoed))
+        }
+        ProposalState::Cancelled => {
+            msg!("Proposal was cancelled");
+            Err(ProgramError::from(PollError::ProposalCancelled))
+        }
+        ProposalState::None | ProposalState::Pending => {
+            msg!("Proposal did not pass");
+            Err(ProgramError::from(PollError::ProposalNotPassed))
+        }
+    }
+}
+
+//hash a voter commits to on commit-reveal polls
+pub fn vote_commitment(option_id: u8, salt: &[u8; 32]) -> [u8; 32] {
+    hashv(&[&[option_id], salt]).to_bytes()
+}
+
+//count a committed ballot once the option and salt match its commitment,
+//the commitment is cleared so a ballot is only revealed once
+pub fn reveal_ballot(
+    poll: &mut Poll,
+    voter: &mut PollVoter,
+    option_id: u8,
+    salt: &[u8; 32],
+) -> ProgramResult {
+    assert_true(
+        voter.commitment != [0; 32] && voter.commitment == vote_commitment(option_id, salt),
+        ProgramError::from(PollError::CommitmentMismatch),
+        "Option and salt do not match the committed ballot",
+    )?;
+
+    let selections = match poll.allows_choice(option_id) {
+        true => 0,
+        false => PollVoter::option_mask(option_id),
+    };
+    assert_true(
+        (selections != 0 || poll.allows_choice(option_id))
+            && selections & !poll.options_mask() == 0,
+        ProgramError::from(PollError::PollMismatch),
+        "Selected option is not present in poll options",
+    )?;
+
+    voter.option_selected = option_id;
+    voter.selections = selections;
+    voter.commitment = [0; 32];
+    poll.add_voter_votes(voter);
+    poll.unrevealed = poll.unrevealed.saturating_sub(1);
+    Ok(())
+}
+
+//root of the allowlist merkle tree reached from the voter's leaf through proof,
+//pairs are hashed in sorted order so the proof needs no directions
+pub fn allowlist_root(voter: &Pubkey, proof: &[[u8; 32]]) -> [u8; 32] {
+    proof
+        .iter()
+        .fold(hashv(&[voter.as_ref()]).to_bytes(), |node, sibling| {
+            if node <= *sibling {
+                hashv(&[&node, sibling]).to_bytes()
+            } else {
+                hashv(&[sibling, &node]).to_bytes()
+            }
+        })
+}
+
+//allowlisted polls only take votes from voters with a proof to the root
+pub fn assert_allowed(poll: &Poll, voter: &Pubkey, proof: &[[u8; 32]]) -> ProgramResult {
+    assert_true(
+        poll.allowlist_root == [0; 32] || allowlist_root(voter, proof) == poll.allowlist_root,
+        ProgramError::from(PollError::NotOnAllowlist),
+        "Voter is not on the poll's allowlist",
+    )
+}
+
+//voter pdas are refunded to whoever paid their rent, pdas from before the
+//payer was recorded were paid by the voter
+pub fn assert_rent_payer(
+    voter_account: &PollVoter,
+    voter: &Pubkey,
+    rent_payer: &Pubkey,
+) -> ProgramResult {
+    let payer = match voter_account.payer {
+        payer if payer == Pubkey::default() => *voter,
+        payer => payer,
+    };
+    assert_true(
+        *rent_payer == payer,
+        ProgramError::from(PollError::RentPayerMismatch),
+        "Rent payer does not match the voter pda",
+    )
+}
+
+//a checked ballot, recorded for the voter and the voters that delegated to them
+struct Vote<'b, 'a> {
+    poll_id: u64,
+    selections: u64,
+    ballot: &'b Ballot,
+    //voter, pays for the pdas
+    payer: &'b AccountInfo<'a>,
+    system_program: &'b AccountInfo<'a>,
+    //ballot box of ranked choice polls
+    ballot_box: Option<&'b AccountInfo<'a>>,
+}
+
+//what a voter submitted, checked against the poll's vote mode
+enum Ballot {
+    //one option, or options by preference for ranked choice polls
+    Options(Vec<u8>),
+    //approval voting bitmask
+    Selections(u64),
+    //quadratic votes per option
+    Allocation(Vec<u16>),
+    //hash of the option id and a salt on commit-reveal polls
+    Commitment([u8; 32]),
+}
+
 pub struct Processor {}
 
 impl Processor {
@@ -42,24 +338,85 @@ impl Processor {
         let instr: PollInstruction = PollInstruction::unpack(_instruction_data)?;
         msg!("instr={:?}", instr);
         match instr {
-            PollInstruction::CreatePoll {
-                title_length,
-                title,
-                options_count,
-                options_size,
-                options,
-            } => Self::create_poll(
+            PollInstruction::CreatePoll(args) => Self::create_poll(_accounts, *args, _program_id),
+            PollInstruction::VotePoll {
+                id,
+                option_ids,
+                commitment,
+                proof,
+            } => Self::vote_poll(
                 _accounts,
-                title_length,
-                title,
-                options_count,
-                options_size,
-                options,
+                id,
+                match commitment {
+                    Some(commitment) => Ballot::Commitment(commitment),
+                    None => Ballot::Options(option_ids),
+                },
+                &proof,
+                _program_id,
+            ),
+            PollInstruction::MigratePoll { id } => Self::migrate_poll(_accounts, id, _program_id),
+            PollInstruction::ClosePoll { id } => Self::close_poll(_accounts, id, _program_id),
+            PollInstruction::CloseVoterAccount { id } => {
+                Self::close_voter_account(_accounts, id, _program_id)
+            }
+            PollInstruction::ClosePollAccount { id } => {
+                Self::close_poll_account(_accounts, id, _program_id)
+            }
+            PollInstruction::RetractVote { id } => Self::retract_vote(_accounts, id, _program_id),
+            PollInstruction::ApprovalVotePoll {
+                id,
+                selections,
+                proof,
+            } => Self::vote_poll(
+                _accounts,
+                id,
+                Ballot::Selections(selections),
+                &proof,
+                _program_id,
+            ),
+            PollInstruction::TallyRound { id } => Self::tally_round(_accounts, id, _program_id),
+            PollInstruction::Withdraw { id } => Self::withdraw(_accounts, id, _program_id),
+            PollInstruction::QuadraticVotePoll { id, votes, proof } => Self::vote_poll(
+                _accounts,
+                id,
+                Ballot::Allocation(votes),
+                &proof,
                 _program_id,
             ),
-            PollInstruction::VotePoll { id, option_id } => {
-                Self::vote_poll(_accounts, id, option_id, _program_id)
+            PollInstruction::Delegate { poll } => Self::delegate(_accounts, poll, _program_id),
+            PollInstruction::Undelegate { poll } => Self::undelegate(_accounts, poll, _program_id),
+            PollInstruction::RevealVote {
+                id,
+                option_id,
+                salt,
+            } => Self::reveal_vote(_accounts, id, option_id, &salt, _program_id),
+            PollInstruction::AddProposalInstruction { id, instruction } => {
+                Self::add_proposal_instruction(_accounts, id, instruction, _program_id)
+            }
+            PollInstruction::ExecuteProposal { id } => {
+                Self::execute_proposal(_accounts, id, _program_id)
+            }
+            PollInstruction::CancelProposal { id } => {
+                Self::cancel_proposal(_accounts, id, _program_id)
             }
+            PollInstruction::FundTreasury {
+                creator,
+                mint,
+                amount,
+            } => Self::fund_treasury(_accounts, &creator, &mint, amount, _program_id),
+            PollInstruction::ExecuteSpend { id } => Self::execute_spend(_accounts, id, _program_id),
+            PollInstruction::VetoPoll { id } => Self::veto_poll(_accounts, id, _program_id),
+            PollInstruction::SetGovernanceConfig {
+                min_quorum,
+                min_threshold,
+                min_voting_period,
+            } => Self::set_governance_config(
+                _accounts,
+                min_quorum,
+                min_threshold,
+                min_voting_period,
+                _program_id,
+            ),
         }?;
 
         Ok(())
@@ -67,13 +424,39 @@ impl Processor {
 
     fn create_poll(
         _acccounts: &[AccountInfo],
-        title_length: u8,
-        title: String,
-        options_count: u8,
-        options_size: Vec<u8>,
-        options: Vec<String>,
+        args: CreatePollArgs,
         _program_id: &Pubkey,
     ) -> ProgramResult {
+        let CreatePollArgs {
+            title_length,
+            title,
+            options_count,
+            options_size,
+            options,
+            start_ts,
+            end_ts,
+            allow_revote,
+            vote_mode,
+            max_selections,
+            vote_mint,
+            lock_tokens,
+            credits,
+            allowlist_root,
+            nft_collection,
+            reveal_end_ts,
+            quorum,
+            threshold,
+            pass_option,
+            timelock,
+            council,
+            spend_recipient,
+            spend_mint,
+            spend_amount,
+            veto_threshold,
+            veto_keys,
+            allow_abstain,
+            allow_nota,
+        } = args;
         let accounts_iter = &mut _acccounts.iter();
         //get poll count pda
         let poll_count_account_iter = next_account_info(accounts_iter)?;
@@ -81,13 +464,149 @@ impl Processor {
         let poll_account_iter = next_account_info(accounts_iter)?;
         //get system program
         let system_program_account = next_account_info(accounts_iter)?;
-        //get system program
+        //get payer, polls are created in the payer's namespace
         let payer_account_iter = next_account_info(accounts_iter)?;
         msg!("before create poll count account");
 
+        assert_true(
+            payer_account_iter.is_signer,
+            ProgramError::from(PollError::Unauthorized),
+            "Poll creator has to sign",
+        )?;
+
+        assert_true(
+            (Poll::MIN_OPTIONS..=Poll::MAX_OPTIONS).contains(&options_count),
+            ProgramError::from(PollError::InvalidOptionsCount),
+            "Polls need 2 to 64 options",
+        )?;
+
+        //lengths are in bytes, multibyte text takes more of the space
+        assert_true(
+            title.len() <= POLL_TITLE_SIZE
+                && options
+                    .iter()
+                    .all(|option| option.len() <= POLL_OPTION_SIZE),
+            ProgramError::from(PollError::InvalidInstrucitonData),
+            "Titles are at most 100 bytes, options at most 50",
+        )?;
+
+        assert_true(
+            start_ts == 0 || end_ts == 0 || start_ts < end_ts,
+            ProgramError::from(PollError::InvalidVotingWindow),
+            "Voting has to start before it ends",
+        )?;
+
+        assert_true(
+            !lock_tokens || vote_mint != Pubkey::default(),
+            ProgramError::from(PollError::InvalidInstrucitonData),
+            "Only token weighted polls can lock tokens",
+        )?;
+
+        assert_true(
+            max_selections <= options_count,
+            ProgramError::from(PollError::InvalidOptionsCount),
+            "Max selections can not be more than the options count",
+        )?;
+
+        assert_true(
+            vote_mode != VoteMode::Quadratic || credits > 0,
+            ProgramError::from(PollError::InvalidInstrucitonData),
+            "Quadratic polls need a voice credit budget",
+        )?;
+
+        assert_true(
+            nft_collection == Pubkey::default() || vote_mint == Pubkey::default(),
+            ProgramError::from(PollError::InvalidInstrucitonData),
+            "Nft gated polls can not be token weighted",
+        )?;
+
+        assert_true(
+            reveal_end_ts == 0 || (end_ts != 0 && end_ts < reveal_end_ts),
+            ProgramError::from(PollError::InvalidVotingWindow),
+            "Ballots have to be revealed after voting ends",
+        )?;
+
+        assert_true(
+            reveal_end_ts == 0 || vote_mode == VoteMode::Single,
+            ProgramError::from(PollError::VoteModeMismatch),
+            "Only single choice polls can commit ballots",
+        )?;
+
+        assert_true(
+            threshold <= 10_000,
+            ProgramError::from(PollError::InvalidInstrucitonData),
+            "Threshold is in basis points, at most 10000",
+        )?;
+
+        assert_true(
+            pass_option <= options_count,
+            ProgramError::from(PollError::PollMismatch),
+            "Pass option is not present in poll options",
+        )?;
+
+        assert_true(
+            timelock >= 0,
+            ProgramError::from(PollError::InvalidVotingWindow),
+            "Timelock can not be negative",
+        )?;
+
+        assert_true(
+            spend_amount == 0 || spend_recipient != Pubkey::default(),
+            ProgramError::from(PollError::InvalidSpend),
+            "Spend polls need a recipient",
+        )?;
+
+        //the treasury is only spent after a full voting window
+        assert_true(
+            spend_amount == 0 || end_ts != 0,
+            ProgramError::from(PollError::InvalidSpend),
+            "Spend polls need a voting end",
+        )?;
+
+        //a single key is a 1 of 1 veto authority
+        assert_true(
+            veto_keys.len() <= POLL_VETO_KEYS
+                && (veto_threshold as usize) <= veto_keys.len()
+                && (veto_threshold == 0) == veto_keys.is_empty()
+                && !veto_keys.contains(&Pubkey::default())
+                && veto_keys
+                    .iter()
+                    .enumerate()
+                    .all(|(i, key)| !veto_keys[..i].contains(key)),
+            ProgramError::from(PollError::InvalidVetoAuthority),
+            "Veto threshold has to be between 1 and the number of distinct veto keys",
+        )?;
+
+        //ranked ballots are tallied from the ballot box, they can only rank options
+        assert_true(
+            vote_mode != VoteMode::Ranked || !(allow_abstain || allow_nota),
+            ProgramError::from(PollError::VoteModeMismatch),
+            "Ranked choice polls can not offer abstain or none of the above",
+        )?;
+
+        //spend polls pay out of the treasury, they meet the creator's
+        //governance config over the voting window still ahead
+        if spend_amount > 0 {
+            let governance_config_iter = next_account_info(accounts_iter)?;
+            let governance_config = Self::load_governance_config(
+                governance_config_iter,
+                payer_account_iter.key,
+                _program_id,
+            )?;
+            assert_governance_rules(
+                &governance_config,
+                quorum,
+                threshold,
+                end_ts.saturating_sub(start_ts.max(Clock::get()?.unix_timestamp)),
+            )?;
+        }
+
         //check if poll account is empty
         if poll_count_account_iter.data_is_empty() {
-            let (_, bump) = Pubkey::find_program_address(&[POLL_COUNT_SEED], _program_id);
+            let (_, bump) = Pubkey::find_program_address(
+                &[POLL_COUNT_SEED, payer_account_iter.key.as_ref()],
+                _program_id,
+            );
             //create new account
             // payer
             // pda key
@@ -105,7 +624,7 @@ impl Processor {
                     poll_count_account_iter.clone(),
                     system_program_account.clone(),
                 ],
-                &[&[POLL_COUNT_SEED, &[bump]]],
+                &[&[POLL_COUNT_SEED, payer_account_iter.key.as_ref(), &[bump]]],
             )?;
         }
 
@@ -128,7 +647,10 @@ impl Processor {
 
         if !poll_count_account.is_initialized() {
             //ini poll account
-            let (_, bump) = Pubkey::find_program_address(&[POLL_COUNT_SEED], _program_id);
+            let (_, bump) = Pubkey::find_program_address(
+                &[POLL_COUNT_SEED, payer_account_iter.key.as_ref()],
+                _program_id,
+            );
 
             poll_count_account.is_initialized = true;
             poll_count_account.count = 0;
@@ -136,7 +658,11 @@ impl Processor {
         }
 
         let poll_count_pda = Pubkey::create_program_address(
-            &[POLL_COUNT_SEED, &[poll_count_account.bump]],
+            &[
+                POLL_COUNT_SEED,
+                payer_account_iter.key.as_ref(),
+                &[poll_count_account.bump],
+            ],
             _program_id,
         )?;
 
@@ -148,26 +674,22 @@ impl Processor {
         )?;
 
         //get next poll number
-        poll_count_account.count = poll_count_account.count + 1;
-        assert_true(
-            poll_count_account.count != 0,
-            ProgramError::from(PollError::PollsOverflow),
-            "Only 255 polls allowed as of now!",
-        )?;
+        poll_count_account.count = poll_count_account
+            .count
+            .checked_add(1)
+            .ok_or(ProgramError::from(PollError::PollsOverflow))?;
+        let mut poll_seeds = poll_seeds(payer_account_iter.key, poll_count_account.count);
+        let (pda, bump) = Pubkey::find_program_address(&seed_refs(&poll_seeds), _program_id);
+        poll_seeds.push(vec![bump]);
 
         if poll_account_iter.data_is_empty() {
-            let (_, bump) = Pubkey::find_program_address(
-                &[POLL_SEED, &[poll_count_account.count]],
-                _program_id,
-            );
-
             //create pda by invoke
             invoke_signed(
                 &system_instruction::create_account(
                     payer_account_iter.key,
                     poll_account_iter.key,
-                    Rent::get()?.minimum_balance(Poll::SIZE),
-                    Poll::SIZE as u64,
+                    Rent::get()?.minimum_balance(Poll::size(options_count)),
+                    Poll::size(options_count) as u64,
                     _program_id,
                 ),
                 &[
@@ -175,7 +697,7 @@ impl Processor {
                     poll_account_iter.clone(),
                     system_program_account.clone(),
                 ],
-                &[&[POLL_SEED, &[poll_count_account.count], &[bump]]],
+                &[&seed_refs(&poll_seeds)],
             )?;
         }
         let mut poll_account = Poll::unpack_unchecked(&poll_account_iter.try_borrow_data()?)?;
@@ -198,35 +720,67 @@ impl Processor {
             "Poll already created for this id!",
         )?;
 
-        let (pda, bump) =
-            Pubkey::find_program_address(&[POLL_SEED, &[poll_count_account.count]], _program_id);
-
         if !poll_account.is_initialized() {
             poll_account.is_initialized = true;
+            poll_account.creator = *payer_account_iter.key;
             poll_account.id = poll_count_account.count;
-            poll_account.title = format!("{:<width$}", title, width = POLL_TITLE_SIZE);
+            poll_account.title = pad_text(&title, POLL_TITLE_SIZE);
             poll_account.title_length = title_length;
             poll_account.options_count = options_count;
             poll_account.bump = bump;
+            poll_account.start_ts = start_ts;
+            poll_account.end_ts = end_ts;
+            poll_account.allow_revote = allow_revote;
+            poll_account.status = PollStatus::Open;
+            poll_account.winner = 0;
+            poll_account.vote_mode = vote_mode;
+            poll_account.max_selections = match vote_mode {
+                VoteMode::Approval => max_selections,
+                _ => 0,
+            };
+            poll_account.vote_mint = vote_mint;
+            poll_account.lock_tokens = lock_tokens;
+            poll_account.credits = match vote_mode {
+                VoteMode::Quadratic => credits,
+                _ => 0,
+            };
+            poll_account.allowlist_root = allowlist_root;
+            poll_account.nft_collection = nft_collection;
+            poll_account.reveal_end_ts = reveal_end_ts;
+            poll_account.unrevealed = 0;
+            poll_account.quorum = quorum;
+            poll_account.threshold = threshold;
+            poll_account.pass_option = pass_option;
+            poll_account.votes_cast = 0;
+            poll_account.outcome = PollOutcome::Undecided;
+            poll_account.timelock = timelock;
+            poll_account.council = council;
+            //spend polls are pending from the start, other polls once they
+            //have instructions
+            poll_account.proposal_state = if spend_amount > 0 {
+                ProposalState::Pending
+            } else {
+                ProposalState::None
+            };
+            poll_account.execute_after = 0;
+            poll_account.spend_recipient = spend_recipient;
+            poll_account.spend_mint = spend_mint;
+            poll_account.spend_amount = spend_amount;
+            poll_account.veto_threshold = veto_threshold;
+            poll_account.veto_keys = [Pubkey::default(); POLL_VETO_KEYS];
+            poll_account.veto_keys[..veto_keys.len()].copy_from_slice(&veto_keys);
+            poll_account.allow_abstain = allow_abstain;
+            poll_account.allow_nota = allow_nota;
+            poll_account.abstain_votes = 0;
+            poll_account.nota_votes = 0;
             poll_account.options = Vec::new();
             //fill empty text
             for i in 0..options_count as usize {
                 let sz = *options_size.get(i).unwrap();
                 let st = options.get(i).unwrap();
-                let po = PollOption::new(
-                    i as u8,
-                    format!("{:<width$}", &st, width = POLL_OPTION_SIZE),
-                    sz,
-                );
+                let po = PollOption::new(i as u8, pad_text(st, POLL_OPTION_SIZE), sz);
                 poll_account.options.push(po);
             }
-            for i in options_count as usize..Poll::OPTIONS_COINT as usize {
-                poll_account.options.push(PollOption::new(
-                    i as u8,
-                    format!("{:<width$}", "", width = POLL_OPTION_SIZE),
-                    0,
-                ));
-            }
             Poll::pack(poll_account, &mut poll_account_iter.try_borrow_mut_data()?)?;
         }
 
@@ -236,6 +790,20 @@ impl Processor {
             "Poll pdas do not match",
         )?;
 
+        if vote_mode == VoteMode::Ranked {
+            //ballot box account
+            let ballot_box_account_iter = next_account_info(accounts_iter)?;
+            Self::create_ballot_box(
+                ballot_box_account_iter,
+                payer_account_iter,
+                system_program_account,
+                &poll_seeds,
+                poll_count_account.count,
+                options_count,
+                _program_id,
+            )?;
+        }
+
         PollCount::pack(
             poll_count_account,
             &mut poll_count_account_iter.try_borrow_mut_data()?,
@@ -244,10 +812,63 @@ impl Processor {
         Ok(())
     }
 
+    //create the empty ballot box of a ranked choice poll, it grows with every ballot
+    fn create_ballot_box<'a>(
+        ballot_box_account: &AccountInfo<'a>,
+        payer: &AccountInfo<'a>,
+        system_program: &AccountInfo<'a>,
+        poll_seeds: &[Vec<u8>],
+        poll_id: u64,
+        options_count: u8,
+        _program_id: &Pubkey,
+    ) -> ProgramResult {
+        let mut seeds = ballot_box_seeds(poll_seeds);
+        let (pda, bump) = Pubkey::find_program_address(&seed_refs(&seeds), _program_id);
+        seeds.push(vec![bump]);
+
+        assert_true(
+            pda == *ballot_box_account.key,
+            ProgramError::from(PollError::PdaNotMatched),
+            "Ballot box pda does not match",
+        )?;
+
+        let size = BallotBox::size(options_count, 0);
+        invoke_signed(
+            &system_instruction::create_account(
+                payer.key,
+                ballot_box_account.key,
+                Rent::get()?.minimum_balance(size),
+                size as u64,
+                _program_id,
+            ),
+            &[
+                payer.clone(),
+                ballot_box_account.clone(),
+                system_program.clone(),
+            ],
+            &[&seed_refs(&seeds)],
+        )?;
+
+        let ballot_box = BallotBox {
+            is_initialized: true,
+            poll_id,
+            bump,
+            options_count,
+            ballots_count: 0,
+            tally_done: false,
+            round: 0,
+            cursor: 0,
+            eliminated: 0,
+            round_votes: vec![0; options_count as usize],
+        };
+        BallotBox::pack(ballot_box, &mut ballot_box_account.try_borrow_mut_data()?)
+    }
+
     fn vote_poll(
         _accounts: &[AccountInfo],
-        poll_id: u8,
-        option_id: u8,
+        poll_id: u64,
+        ballot: Ballot,
+        proof: &[[u8; 32]],
         _program_id: &Pubkey,
     ) -> ProgramResult {
         //accounts
@@ -265,131 +886,1953 @@ impl Processor {
         //system program
         let system_program_account = next_account_info(accounts_iter)?;
 
+        //voter pays for and owns the vote, nobody can vote on their behalf
+        assert_true(
+            voter_iter.is_signer,
+            ProgramError::from(PollError::VoterNotSigner),
+            "Voter has to sign the vote",
+        )?;
+
         //poll pda
-        let mut poll_pda = Poll::unpack_unchecked(&poll_pda_account_iter.try_borrow_data()?)?;
+        let (mut poll_pda, poll_seeds) =
+            Self::load_poll(poll_pda_account_iter, poll_id, _program_id)?;
 
-        //get poll account
-        let poll_pda_account = Pubkey::create_program_address(
-            &[POLL_SEED, &[poll_pda.id], &[poll_pda.bump.clone()]],
-            _program_id,
-        )?;
+        assert_poll_open(&poll_pda)?;
 
+        //commit-reveal polls only take commitments, and only they do
+        let committed = matches!(ballot, Ballot::Commitment(_));
         assert_true(
-            poll_pda_account == *poll_pda_account_iter.key,
-            ProgramError::from(PollError::PdaNotMatched),
-            "Pda does not match",
+            committed == (poll_pda.reveal_end_ts != 0),
+            ProgramError::from(PollError::VoteModeMismatch),
+            "Commit-reveal polls take committed ballots only",
         )?;
 
+        //votes are counted as a bitmask of options,
+        //ranked ballots count for their first choice,
+        //committed ballots, abstentions and none of the above count for no option
+        let choice = match &ballot {
+            Ballot::Options(option_ids)
+                if option_ids.len() == 1 && poll_pda.allows_choice(option_ids[0]) =>
+            {
+                Some(option_ids[0])
+            }
+            _ => None,
+        };
+        let selections = match (poll_pda.vote_mode, &ballot) {
+            (VoteMode::Single, Ballot::Commitment(_)) => 0,
+            _ if choice.is_some() => 0,
+            (VoteMode::Single, Ballot::Options(option_ids)) if option_ids.len() == 1 => {
+                PollVoter::option_mask(option_ids[0])
+            }
+            (VoteMode::Approval, Ballot::Selections(selections)) => *selections,
+            (VoteMode::Ranked, Ballot::Options(option_ids)) => {
+                let ranked = option_ids.iter().fold(0, |mask, option_id| {
+                    mask | PollVoter::option_mask(*option_id)
+                });
+                assert_true(
+                    ranked.count_ones() as usize == option_ids.len()
+                        && ranked & !poll_pda.options_mask() == 0,
+                    ProgramError::from(PollError::InvalidRanking),
+                    "Ranking has to list distinct poll options",
+                )?;
+                PollVoter::option_mask(option_ids[0])
+            }
+            (VoteMode::Quadratic, Ballot::Allocation(votes)) => {
+                assert_true(
+                    votes.len() <= poll_pda.options_count as usize,
+                    ProgramError::from(PollError::PollMismatch),
+                    "Votes given for options not present in the poll",
+                )?;
+                assert_true(
+                    poll_pda.within_credits(votes),
+                    ProgramError::from(PollError::CreditBudgetExceeded),
+                    "Votes cost more voice credits than the voter has",
+                )?;
+                votes
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, votes)| **votes > 0)
+                    .fold(0, |mask, (i, _)| mask | PollVoter::option_mask(i as u8 + 1))
+            }
+            _ => {
+                msg!("Ballot does not match the poll's vote mode");
+                return Err(ProgramError::from(PollError::VoteModeMismatch));
+            }
+        };
+
         assert_true(
-            poll_pda.id == poll_id,
+            (selections != 0 || committed || choice.is_some())
+                && selections & !poll_pda.options_mask() == 0,
             ProgramError::from(PollError::PollMismatch),
-            "Poll account does not match",
+            "Selected option is not present in poll options",
         )?;
 
         assert_true(
-            option_id > 0 && option_id <= poll_pda.options_count,
-            ProgramError::from(PollError::PollMismatch),
-            "Selected option is not present in poll options",
+            poll_pda.max_selections == 0
+                || selections.count_ones() <= poll_pda.max_selections as u32,
+            ProgramError::from(PollError::TooManySelections),
+            "Too many options selected",
         )?;
 
+        //ballot box account, ranked choice polls only
+        let ballot_box_account_iter = match poll_pda.vote_mode {
+            VoteMode::Ranked => Some(next_account_info(accounts_iter)?),
+            _ => None,
+        };
+
+        //voter pda is seeded by the poll's seeds and the voter,
+        //on nft gated polls by the nft mint so each nft votes once
+        let voter_key = if poll_pda.nft_collection != Pubkey::default() {
+            //voter nft token account
+            let nft_account_iter = next_account_info(accounts_iter)?;
+            //nft metadata account
+            let metadata_account_iter = next_account_info(accounts_iter)?;
+            let nft = Self::load_nft(nft_account_iter, voter_iter.key)?;
+            Self::assert_nft_collection(
+                metadata_account_iter,
+                &nft.mint,
+                &poll_pda.nft_collection,
+            )?;
+            nft.mint
+        } else {
+            *voter_iter.key
+        };
+        let mut voter_seeds = poll_seeds.clone();
+        voter_seeds.push(voter_key.to_bytes().to_vec());
+
         if voter_pda_account_iter.data_is_empty() {
-            let (pda, bump) = Pubkey::find_program_address(
-                &[
-                    POLL_SEED,
-                    &[poll_pda.id],
-                    &[poll_pda.bump.clone()],
-                    voter_iter.key.as_ref(),
-                ],
-                _program_id,
-            );
+            //voters on an allowlisted poll prove membership before their pda is created
+            assert_allowed(&poll_pda, voter_iter.key, proof)?;
+        }
 
-            msg!("pda={:?}", pda);
-            msg!(
-                "voter_pda_account_iter.key={:?}",
-                voter_pda_account_iter.key
-            );
+        //token weighted polls count the voter's balance of the vote mint
+        let weight = if poll_pda.vote_mint != Pubkey::default() {
+            //voter token account
+            let token_account_iter = next_account_info(accounts_iter)?;
+            let token_account =
+                Self::load_token_account(token_account_iter, voter_iter.key, &poll_pda.vote_mint)?;
+            if poll_pda.lock_tokens {
+                Self::lock_tokens(
+                    accounts_iter,
+                    voter_iter,
+                    token_account_iter,
+                    system_program_account,
+                    &poll_pda.vote_mint,
+                    &voter_seeds,
+                    _program_id,
+                )?
+            } else {
+                token_account.amount
+            }
+        } else {
+            1
+        };
+
+        let vote = Vote {
+            poll_id,
+            selections,
+            ballot: &ballot,
+            payer: voter_iter,
+            system_program: system_program_account,
+            ballot_box: ballot_box_account_iter,
+        };
+
+        Self::record_vote(
+            &mut poll_pda,
+            &poll_seeds,
+            voter_pda_account_iter,
+            &voter_seeds,
+            weight,
+            &vote,
+            _program_id,
+        )?;
 
+        //the voter also votes for everyone who delegated to them
+        while let Ok(delegation_account_iter) = next_account_info(accounts_iter) {
             assert_true(
-                pda == *voter_pda_account_iter.key,
-                ProgramError::from(PollError::PdaNotMatched),
-                "Poll account does not match",
+                poll_pda.allowlist_root == [0; 32]
+                    && poll_pda.nft_collection == Pubkey::default()
+                    && !poll_pda.lock_tokens
+                    && !committed,
+                ProgramError::from(PollError::DelegationNotAllowed),
+                "Votes on this poll can not be delegated",
             )?;
 
-            invoke_signed(
-                &system_instruction::create_account(
-                    voter_iter.key,
-                    voter_pda_account_iter.key,
-                    Rent::get()?.minimum_balance(PollVoter::SIZE),
-                    PollVoter::SIZE as u64,
+            let delegation = Self::load_delegation(
+                delegation_account_iter,
+                voter_iter.key,
+                poll_pda_account_iter.key,
+                _program_id,
+            )?;
+
+            if delegation.poll == Pubkey::default() {
+                //delegation for the poll, it would take precedence
+                let poll_delegation_account_iter = next_account_info(accounts_iter)?;
+                let (pda, _) = Pubkey::find_program_address(
+                    &seed_refs(&delegation_seeds(
+                        &delegation.delegator,
+                        poll_pda_account_iter.key,
+                    )),
                     _program_id,
-                ),
-                &[
-                    voter_iter.clone(),
-                    voter_pda_account_iter.clone(),
-                    system_program_account.clone(),
-                ],
-                &[&[
-                    POLL_SEED,
-                    &[poll_pda.id],
-                    &[poll_pda.bump.clone()],
-                    voter_iter.key.as_ref(),
-                    &[bump],
-                ]],
+                );
+                assert_true(
+                    pda == *poll_delegation_account_iter.key
+                        && poll_delegation_account_iter.data_is_empty(),
+                    ProgramError::from(PollError::DelegationNotAllowed),
+                    "Delegator delegated this poll to someone else",
+                )?;
+            }
+
+            //delegator voter pda
+            let delegator_pda_account_iter = next_account_info(accounts_iter)?;
+
+            let weight = if poll_pda.vote_mint != Pubkey::default() {
+                //delegator token account
+                let token_account_iter = next_account_info(accounts_iter)?;
+                Self::load_token_account(
+                    token_account_iter,
+                    &delegation.delegator,
+                    &poll_pda.vote_mint,
+                )?
+                .amount
+            } else {
+                1
+            };
+
+            //delegators that voted themselves (or were voted for) keep their vote
+            if !delegator_pda_account_iter.data_is_empty() {
+                msg!("{} already voted", delegation.delegator);
+                continue;
+            }
+
+            let mut delegator_seeds = poll_seeds.clone();
+            delegator_seeds.push(delegation.delegator.to_bytes().to_vec());
+
+            Self::record_vote(
+                &mut poll_pda,
+                &poll_seeds,
+                delegator_pda_account_iter,
+                &delegator_seeds,
+                weight,
+                &vote,
+                _program_id,
             )?;
         }
-        //Create Voter Account
-        let mut voter_account =
-            PollVoter::unpack_unchecked(&voter_pda_account_iter.try_borrow_data()?)?;
-        // {
-        //     Ok(pda) => pda,
-        //     Err(err) => {
-        //         if err == ProgramError::InvalidAccountData {
-        //             //create voter pda
 
-        //             PollVoter::unpack_unchecked(&voter_pda_account_iter.try_borrow_data()?)?
-        //         } else {
-        //             panic!("Something went wrong!");
-        //         }
-        //     }
-        // };
+        Poll::pack(poll_pda, &mut poll_pda_account_iter.try_borrow_mut_data()?)?;
 
+        Ok(())
+    }
+
+    //create the voter pda if needed and count the vote with weight,
+    //a voter that voted already has their vote replaced
+    fn record_vote<'a>(
+        poll: &mut Poll,
+        poll_seeds: &[Vec<u8>],
+        voter_pda_account: &AccountInfo<'a>,
+        voter_seeds: &[Vec<u8>],
+        weight: u64,
+        vote: &Vote<'_, 'a>,
+        _program_id: &Pubkey,
+    ) -> ProgramResult {
         assert_true(
-            !voter_account.is_initialized(),
-            ProgramError::from(PollError::AlreadyVoted),
-            "Already voted for this poll",
+            weight > 0,
+            ProgramError::from(PollError::NoVotingWeight),
+            "Voter holds none of the poll's token",
         )?;
 
-        let (voter_pda, bump) = Pubkey::find_program_address(
-            &[
-                POLL_SEED,
-                &[poll_pda.id],
-                &[poll_pda.bump.clone()],
-                voter_iter.key.as_ref(),
-            ],
-            _program_id,
-        );
-
-        voter_account.is_initialized = true;
-        voter_account.poll_id = poll_id;
-        voter_account.option_selected = option_id;
-        voter_account.bump = bump;
+        let (voter_pda, bump) = Pubkey::find_program_address(&seed_refs(voter_seeds), _program_id);
 
         assert_true(
-            voter_pda == *voter_pda_account_iter.key,
+            voter_pda == *voter_pda_account.key,
             ProgramError::from(PollError::PdaNotMatched),
             "Pda does not match",
         )?;
 
-        PollVoter::pack(
-            voter_account,
-            &mut voter_pda_account_iter.try_borrow_mut_data()?,
-        )?;
+        if voter_pda_account.data_is_empty() {
+            let voter_size = PollVoter::size(poll.vote_mode, poll.options_count);
+            let mut signer_seeds = voter_seeds.to_vec();
+            signer_seeds.push(vec![bump]);
 
-        poll_pda.add_vote(option_id - 1, 1);
-        Poll::pack(poll_pda, &mut poll_pda_account_iter.try_borrow_mut_data()?)?;
-
-        Ok(())
-    }
+            invoke_signed(
+                &system_instruction::create_account(
+                    vote.payer.key,
+                    voter_pda_account.key,
+                    Rent::get()?.minimum_balance(voter_size),
+                    voter_size as u64,
+                    _program_id,
+                ),
+                &[
+                    vote.payer.clone(),
+                    voter_pda_account.clone(),
+                    vote.system_program.clone(),
+                ],
+                &[&seed_refs(&signer_seeds)],
+            )?;
+        }
+
+        let mut voter_account = if voter_pda_account.data_len() == PollVoter::LEGACY_SIZE {
+            PollVoter::unpack_legacy(&voter_pda_account.try_borrow_data()?)?
+        } else {
+            PollVoter::unpack_unchecked(&voter_pda_account.try_borrow_data()?)?
+        };
+
+        if !voter_account.is_initialized() {
+            //the rent is refunded to whoever paid for the pda
+            voter_account.payer = *vote.payer.key;
+        } else {
+            assert_true(
+                poll.allow_revote,
+                ProgramError::from(PollError::AlreadyVoted),
+                "Already voted for this poll",
+            )?;
+            //move the vote from the previous choices
+            poll.remove_voter_votes(&voter_account);
+            if voter_account.commitment != [0; 32] {
+                poll.unrevealed = poll.unrevealed.saturating_sub(1);
+            }
+        }
+
+        if let (Some(ballot_box_account), Ballot::Options(option_ids)) =
+            (vote.ballot_box, vote.ballot)
+        {
+            let mut ballot_box =
+                Self::load_ballot_box(ballot_box_account, poll_seeds, _program_id)?;
+
+            //a revote overwrites the voter's ballot, new voters get the next slot
+            if !voter_account.is_initialized() {
+                voter_account.ballot = ballot_box.ballots_count;
+                ballot_box.ballots_count = ballot_box
+                    .ballots_count
+                    .checked_add(1)
+                    .ok_or(ProgramError::InvalidAccountData)?;
+                Self::resize_account(
+                    ballot_box_account,
+                    vote.payer,
+                    vote.system_program,
+                    BallotBox::size(ballot_box.options_count, ballot_box.ballots_count),
+                )?;
+            }
+
+            let mut ballot_box_data = ballot_box_account.try_borrow_mut_data()?;
+            BallotBox::write_ballot(
+                ballot_box_data
+                    .get_mut(ballot_box.ballot_range(voter_account.ballot))
+                    .ok_or(ProgramError::InvalidAccountData)?,
+                weight,
+                option_ids,
+            );
+            BallotBox::pack(ballot_box, &mut ballot_box_data)?;
+        }
+
+        voter_account.is_initialized = true;
+        voter_account.poll_id = vote.poll_id;
+        voter_account.option_selected = match (poll.vote_mode, vote.ballot) {
+            (_, Ballot::Options(option_ids)) if vote.selections == 0 => option_ids[0],
+            (VoteMode::Approval, _) | (_, Ballot::Commitment(_)) => 0,
+            _ => vote.selections.trailing_zeros() as u8 + 1,
+        };
+        voter_account.bump = bump;
+        voter_account.selections = vote.selections;
+        voter_account.weight = weight;
+        voter_account.commitment = match vote.ballot {
+            Ballot::Commitment(commitment) => {
+                poll.unrevealed += 1;
+                *commitment
+            }
+            _ => [0; 32],
+        };
+        voter_account.allocation = match vote.ballot {
+            Ballot::Allocation(votes) => {
+                let mut allocation = votes.clone();
+                allocation.resize(poll.options_count as usize, 0);
+                allocation
+            }
+            //quadratic voters abstaining overwrite their previous allocation
+            _ if poll.vote_mode == VoteMode::Quadratic => vec![0; poll.options_count as usize],
+            _ => Vec::new(),
+        };
+        poll.add_voter_votes(&voter_account);
+
+        PollVoter::pack(voter_account, &mut voter_pda_account.try_borrow_mut_data()?)
+    }
+
+    fn delegate(_accounts: &[AccountInfo], poll: Pubkey, _program_id: &Pubkey) -> ProgramResult {
+        let accounts_iter = &mut _accounts.iter();
+        //delegation pda
+        let delegation_account_iter = next_account_info(accounts_iter)?;
+        //delegator account
+        let delegator_iter = next_account_info(accounts_iter)?;
+        //delegate account
+        let delegate_iter = next_account_info(accounts_iter)?;
+        //system program
+        let system_program_account = next_account_info(accounts_iter)?;
+
+        assert_true(
+            delegator_iter.is_signer,
+            ProgramError::from(PollError::VoterNotSigner),
+            "Delegator has to sign",
+        )?;
+
+        assert_true(
+            delegate_iter.key != delegator_iter.key,
+            ProgramError::from(PollError::InvalidInstrucitonData),
+            "Voters can not delegate to themselves",
+        )?;
+
+        let seeds = delegation_seeds(delegator_iter.key, &poll);
+        let (pda, bump) = Pubkey::find_program_address(&seed_refs(&seeds), _program_id);
+
+        assert_true(
+            pda == *delegation_account_iter.key,
+            ProgramError::from(PollError::PdaNotMatched),
+            "Delegation pda does not match",
+        )?;
+
+        if delegation_account_iter.data_is_empty() {
+            let mut signer_seeds = seeds;
+            signer_seeds.push(vec![bump]);
+            invoke_signed(
+                &system_instruction::create_account(
+                    delegator_iter.key,
+                    delegation_account_iter.key,
+                    Rent::get()?.minimum_balance(Delegation::SIZE),
+                    Delegation::SIZE as u64,
+                    _program_id,
+                ),
+                &[
+                    delegator_iter.clone(),
+                    delegation_account_iter.clone(),
+                    system_program_account.clone(),
+                ],
+                &[&seed_refs(&signer_seeds)],
+            )?;
+        }
+
+        let delegation = Delegation {
+            is_initialized: true,
+            delegator: *delegator_iter.key,
+            delegate: *delegate_iter.key,
+            poll,
+            bump,
+        };
+        Delegation::pack(
+            delegation,
+            &mut delegation_account_iter.try_borrow_mut_data()?,
+        )?;
+
+        msg!(
+            "{} delegated to {} on poll {}",
+            delegator_iter.key,
+            delegate_iter.key,
+            poll
+        );
+        Ok(())
+    }
+
+    fn undelegate(_accounts: &[AccountInfo], poll: Pubkey, _program_id: &Pubkey) -> ProgramResult {
+        let accounts_iter = &mut _accounts.iter();
+        //delegation pda
+        let delegation_account_iter = next_account_info(accounts_iter)?;
+        //delegator account
+        let delegator_iter = next_account_info(accounts_iter)?;
+
+        assert_true(
+            delegator_iter.is_signer,
+            ProgramError::from(PollError::VoterNotSigner),
+            "Delegator has to sign",
+        )?;
+
+        let (pda, _) = Pubkey::find_program_address(
+            &seed_refs(&delegation_seeds(delegator_iter.key, &poll)),
+            _program_id,
+        );
+
+        assert_true(
+            pda == *delegation_account_iter.key && !delegation_account_iter.data_is_empty(),
+            ProgramError::from(PollError::PdaNotMatched),
+            "Delegation pda does not match",
+        )?;
+
+        Self::close_account(delegation_account_iter, delegator_iter)
+    }
+
+    //read a delegation to delegate that covers the poll
+    fn load_delegation(
+        delegation_account: &AccountInfo,
+        delegate: &Pubkey,
+        poll: &Pubkey,
+        _program_id: &Pubkey,
+    ) -> Result<Delegation, ProgramError> {
+        assert_true(
+            delegation_account.owner == _program_id,
+            ProgramError::from(PollError::NotInitialized),
+            "Delegation is not owned by the program",
+        )?;
+
+        let delegation = Delegation::unpack(&delegation_account.try_borrow_data()?)?;
+
+        let mut seeds = delegation_seeds(&delegation.delegator, &delegation.poll);
+        seeds.push(vec![delegation.bump]);
+        let pda = Pubkey::create_program_address(&seed_refs(&seeds), _program_id)?;
+
+        assert_true(
+            pda == *delegation_account.key,
+            ProgramError::from(PollError::PdaNotMatched),
+            "Delegation pda does not match",
+        )?;
+
+        assert_true(
+            delegation.delegate == *delegate,
+            ProgramError::from(PollError::Unauthorized),
+            "Voter is not the delegate",
+        )?;
+
+        assert_true(
+            delegation.poll == Pubkey::default() || delegation.poll == *poll,
+            ProgramError::from(PollError::PollMismatch),
+            "Delegation is for another poll",
+        )?;
+
+        Ok(delegation)
+    }
+
+    fn retract_vote(
+        _accounts: &[AccountInfo],
+        poll_id: u64,
+        _program_id: &Pubkey,
+    ) -> ProgramResult {
+        let accounts_iter = &mut _accounts.iter();
+        //poll pda
+        let poll_pda_account_iter = next_account_info(accounts_iter)?;
+        //voter pda
+        let voter_pda_account_iter = next_account_info(accounts_iter)?;
+        //voter account
+        let voter_iter = next_account_info(accounts_iter)?;
+        //rent payer account
+        let rent_payer_iter = next_account_info(accounts_iter)?;
+
+        assert_true(
+            voter_iter.is_signer,
+            ProgramError::from(PollError::VoterNotSigner),
+            "Voter has to sign",
+        )?;
+
+        let (mut poll_pda, poll_seeds) =
+            Self::load_poll(poll_pda_account_iter, poll_id, _program_id)?;
+
+        assert_poll_open(&poll_pda)?;
+
+        //ballot box account, ranked choice polls only
+        let ballot_box_account_iter = match poll_pda.vote_mode {
+            VoteMode::Ranked => Some(next_account_info(accounts_iter)?),
+            _ => None,
+        };
+
+        //the holder of the nft retracts its vote on nft gated polls
+        let voter_key = if poll_pda.nft_collection != Pubkey::default() {
+            //voter nft token account
+            Self::load_nft(next_account_info(accounts_iter)?, voter_iter.key)?.mint
+        } else {
+            *voter_iter.key
+        };
+
+        let voter_account = Self::load_voter(
+            voter_pda_account_iter,
+            &voter_key,
+            poll_seeds.clone(),
+            _program_id,
+        )?;
+
+        assert_rent_payer(&voter_account, voter_iter.key, rent_payer_iter.key)?;
+
+        if let Some(ballot_box_account_iter) = ballot_box_account_iter {
+            //the ballot is wiped and counts as exhausted
+            let ballot_box =
+                Self::load_ballot_box(ballot_box_account_iter, &poll_seeds, _program_id)?;
+            BallotBox::write_ballot(
+                ballot_box_account_iter
+                    .try_borrow_mut_data()?
+                    .get_mut(ballot_box.ballot_range(voter_account.ballot))
+                    .ok_or(ProgramError::InvalidAccountData)?,
+                0,
+                &[],
+            );
+        }
+
+        poll_pda.remove_voter_votes(&voter_account);
+        if voter_account.commitment != [0; 32] {
+            poll_pda.unrevealed = poll_pda.unrevealed.saturating_sub(1);
+        }
+        Poll::pack(poll_pda, &mut poll_pda_account_iter.try_borrow_mut_data()?)?;
+
+        Self::close_account(voter_pda_account_iter, rent_payer_iter)
+    }
+
+    fn reveal_vote(
+        _accounts: &[AccountInfo],
+        poll_id: u64,
+        option_id: u8,
+        salt: &[u8; 32],
+        _program_id: &Pubkey,
+    ) -> ProgramResult {
+        let accounts_iter = &mut _accounts.iter();
+        //poll pda
+        let poll_pda_account_iter = next_account_info(accounts_iter)?;
+        //voter pda
+        let voter_pda_account_iter = next_account_info(accounts_iter)?;
+        //voter account
+        let voter_iter = next_account_info(accounts_iter)?;
+
+        assert_true(
+            voter_iter.is_signer,
+            ProgramError::from(PollError::VoterNotSigner),
+            "Voter has to sign",
+        )?;
+
+        let (mut poll_pda, poll_seeds) =
+            Self::load_poll(poll_pda_account_iter, poll_id, _program_id)?;
+
+        assert_reveal_open(&poll_pda)?;
+
+        //the holder of the nft reveals its ballot on nft gated polls
+        let voter_key = if poll_pda.nft_collection != Pubkey::default() {
+            //voter nft token account
+            Self::load_nft(next_account_info(accounts_iter)?, voter_iter.key)?.mint
+        } else {
+            *voter_iter.key
+        };
+
+        let mut voter_account =
+            Self::load_voter(voter_pda_account_iter, &voter_key, poll_seeds, _program_id)?;
+
+        reveal_ballot(&mut poll_pda, &mut voter_account, option_id, salt)?;
+
+        PollVoter::pack(
+            voter_account,
+            &mut voter_pda_account_iter.try_borrow_mut_data()?,
+        )?;
+        Poll::pack(poll_pda, &mut poll_pda_account_iter.try_borrow_mut_data()?)?;
+
+        Ok(())
+    }
+
+    fn close_poll(_accounts: &[AccountInfo], poll_id: u64, _program_id: &Pubkey) -> ProgramResult {
+        let accounts_iter = &mut _accounts.iter();
+        //poll pda
+        let poll_pda_account_iter = next_account_info(accounts_iter)?;
+        //creator account
+        let creator_iter = next_account_info(accounts_iter)?;
+
+        let (mut poll_pda, _) = Self::load_poll(poll_pda_account_iter, poll_id, _program_id)?;
+
+        assert_creator(&poll_pda, creator_iter)?;
+
+        assert_true(
+            poll_pda.status == PollStatus::Open,
+            ProgramError::from(PollError::PollClosed),
+            "Poll is already closed",
+        )?;
+
+        //executable and spend polls run for their whole voting window
+        assert_true(
+            poll_pda.proposal_state == ProposalState::None
+                || Clock::get()?.unix_timestamp >= poll_pda.end_ts,
+            ProgramError::from(PollError::ProposalLocked),
+            "Executable and spend polls can not be closed before voting ends",
+        )?;
+
+        //committed ballots can be revealed until the reveal window ends
+        assert_true(
+            poll_pda.reveal_end_ts == 0 || Clock::get()?.unix_timestamp >= poll_pda.reveal_end_ts,
+            ProgramError::from(PollError::RevealNotOver),
+            "Ballots can still be revealed",
+        )?;
+
+        poll_pda.finalize();
+        poll_pda.queue_proposal(Clock::get()?.unix_timestamp);
+        msg!(
+            "poll {} closed, winner={}, outcome={:?}",
+            poll_pda.id,
+            poll_pda.winner,
+            poll_pda.outcome
+        );
+        if poll_pda.unrevealed > 0 {
+            msg!("{} ballot(s) not revealed", poll_pda.unrevealed);
+        }
+        Poll::pack(poll_pda, &mut poll_pda_account_iter.try_borrow_mut_data()?)?;
+
+        Ok(())
+    }
+
+    fn close_voter_account(
+        _accounts: &[AccountInfo],
+        poll_id: u64,
+        _program_id: &Pubkey,
+    ) -> ProgramResult {
+        let accounts_iter = &mut _accounts.iter();
+        //poll pda
+        let poll_pda_account_iter = next_account_info(accounts_iter)?;
+        //voter pda
+        let voter_pda_account_iter = next_account_info(accounts_iter)?;
+        //voter account
+        let voter_iter = next_account_info(accounts_iter)?;
+        //rent payer account
+        let rent_payer_iter = next_account_info(accounts_iter)?;
+        //creator account
+        let creator_iter = next_account_info(accounts_iter)?;
+
+        assert_true(
+            voter_iter.is_signer,
+            ProgramError::from(PollError::VoterNotSigner),
+            "Voter has to sign",
+        )?;
+
+        let poll_seeds = if poll_pda_account_iter.data_is_empty() {
+            //poll was already deleted by its creator, it was finalized before that
+            let mut poll_seeds = poll_seeds(creator_iter.key, poll_id);
+            let (pda, bump) = Pubkey::find_program_address(&seed_refs(&poll_seeds), _program_id);
+            assert_true(
+                pda == *poll_pda_account_iter.key,
+                ProgramError::from(PollError::PdaNotMatched),
+                "Pda does not match",
+            )?;
+            poll_seeds.push(vec![bump]);
+            poll_seeds
+        } else {
+            let (poll_pda, poll_seeds) =
+                Self::load_poll(poll_pda_account_iter, poll_id, _program_id)?;
+            assert_true(
+                poll_pda.status == PollStatus::Finalized,
+                ProgramError::from(PollError::PollNotFinalized),
+                "Poll is not finalized yet",
+            )?;
+            poll_seeds
+        };
+
+        //voter pdas of nft gated polls are closed by the holder of the nft
+        let voter_key = match next_account_info(accounts_iter) {
+            //voter nft token account
+            Ok(nft_account_iter) => Self::load_nft(nft_account_iter, voter_iter.key)?.mint,
+            Err(_) => *voter_iter.key,
+        };
+
+        let voter_account =
+            Self::load_voter(voter_pda_account_iter, &voter_key, poll_seeds, _program_id)?;
+
+        assert_rent_payer(&voter_account, voter_iter.key, rent_payer_iter.key)?;
+
+        if voter_account.commitment != [0; 32] {
+            msg!(
+                "voter {} did not reveal their ballot in poll {}",
+                voter_key,
+                poll_id
+            );
+        } else {
+            msg!(
+                "voter {} voted for options {:#b} in poll {}",
+                voter_key,
+                voter_account.selections,
+                poll_id
+            );
+        }
+        Self::close_account(voter_pda_account_iter, rent_payer_iter)
+    }
+
+    fn close_poll_account(
+        _accounts: &[AccountInfo],
+        poll_id: u64,
+        _program_id: &Pubkey,
+    ) -> ProgramResult {
+        let accounts_iter = &mut _accounts.iter();
+        //poll pda
+        let poll_pda_account_iter = next_account_info(accounts_iter)?;
+        //creator account
+        let creator_iter = next_account_info(accounts_iter)?;
+
+        let (poll_pda, poll_seeds) = Self::load_poll(poll_pda_account_iter, poll_id, _program_id)?;
+
+        assert_creator(&poll_pda, creator_iter)?;
+
+        assert_true(
+            poll_pda.status == PollStatus::Finalized,
+            ProgramError::from(PollError::PollNotFinalized),
+            "Poll is not finalized yet",
+        )?;
+
+        if poll_pda.vote_mode == VoteMode::Ranked {
+            //ballot box account
+            let ballot_box_account_iter = next_account_info(accounts_iter)?;
+            let ballot_box =
+                Self::load_ballot_box(ballot_box_account_iter, &poll_seeds, _program_id)?;
+            //the ballots are gone once closed, the runoff has to be over
+            assert_true(
+                ballot_box.tally_done,
+                ProgramError::from(PollError::TallyNotDone),
+                "Ballots are not tallied yet",
+            )?;
+            Self::close_account(ballot_box_account_iter, creator_iter)?;
+        }
+
+        //proposal account, executable polls only
+        if let Ok(proposal_account_iter) = next_account_info(accounts_iter) {
+            Self::load_proposal(proposal_account_iter, &poll_seeds, _program_id)?;
+            Self::close_account(proposal_account_iter, creator_iter)?;
+        }
+
+        poll_pda.log_tally();
+        Self::close_account(poll_pda_account_iter, creator_iter)
+    }
+
+    fn tally_round(_accounts: &[AccountInfo], poll_id: u64, _program_id: &Pubkey) -> ProgramResult {
+        let accounts_iter = &mut _accounts.iter();
+        //poll pda
+        let poll_pda_account_iter = next_account_info(accounts_iter)?;
+        //ballot box account
+        let ballot_box_account_iter = next_account_info(accounts_iter)?;
+
+        let (mut poll_pda, poll_seeds) =
+            Self::load_poll(poll_pda_account_iter, poll_id, _program_id)?;
+
+        assert_true(
+            poll_pda.vote_mode == VoteMode::Ranked,
+            ProgramError::from(PollError::VoteModeMismatch),
+            "Only ranked choice polls are tallied by rounds",
+        )?;
+
+        assert_voting_over(&poll_pda)?;
+
+        let mut ballot_box =
+            Self::load_ballot_box(ballot_box_account_iter, &poll_seeds, _program_id)?;
+
+        assert_true(
+            !ballot_box.tally_done,
+            ProgramError::from(PollError::TallyComplete),
+            "Tally is already complete",
+        )?;
+
+        ballot_box.count_ballots(&ballot_box_account_iter.try_borrow_data()?);
+        msg!(
+            "round {}: counted {} of {} ballots",
+            ballot_box.round,
+            ballot_box.cursor,
+            ballot_box.ballots_count
+        );
+
+        if ballot_box.cursor == ballot_box.ballots_count {
+            if let Some(winner) = ballot_box.finish_round() {
+                poll_pda.winner = winner;
+                poll_pda.status = PollStatus::Finalized;
+                poll_pda.decide_ranked(&ballot_box.round_votes);
+                poll_pda.queue_proposal(Clock::get()?.unix_timestamp);
+                msg!(
+                    "poll {} tallied, winner={}, outcome={:?}",
+                    poll_pda.id,
+                    poll_pda.winner,
+                    poll_pda.outcome
+                );
+                Poll::pack(poll_pda, &mut poll_pda_account_iter.try_borrow_mut_data()?)?;
+            }
+        }
+
+        BallotBox::pack(
+            ballot_box,
+            &mut ballot_box_account_iter.try_borrow_mut_data()?,
+        )
+    }
+
+    //voting weight of a token account, it has to hold the mint and belong to the voter
+    fn load_token_account(
+        token_account: &AccountInfo,
+        voter: &Pubkey,
+        mint: &Pubkey,
+    ) -> Result<TokenAccount, ProgramError> {
+        assert_true(
+            *token_account.owner == spl_token::id(),
+            ProgramError::from(PollError::TokenAccountMismatch),
+            "Token account is not owned by the token program",
+        )?;
+
+        let token = TokenAccount::unpack(&token_account.try_borrow_data()?)?;
+
+        assert_true(
+            token.owner == *voter && token.mint == *mint,
+            ProgramError::from(PollError::TokenAccountMismatch),
+            "Token account does not match the voter or the poll's mint",
+        )?;
+
+        Ok(token)
+    }
+
+    //read the voter's token account of an nft, the voter has to hold the nft
+    fn load_nft(nft_account: &AccountInfo, voter: &Pubkey) -> Result<TokenAccount, ProgramError> {
+        assert_true(
+            *nft_account.owner == spl_token::id(),
+            ProgramError::from(PollError::TokenAccountMismatch),
+            "Nft account is not owned by the token program",
+        )?;
+
+        let nft = TokenAccount::unpack(&nft_account.try_borrow_data()?)?;
+
+        assert_true(
+            nft.owner == *voter && nft.amount == 1,
+            ProgramError::from(PollError::TokenAccountMismatch),
+            "Voter does not hold the nft",
+        )?;
+
+        Ok(nft)
+    }
+
+    //the nft's metadata has to list it as a verified member of the collection
+    fn assert_nft_collection(
+        metadata_account: &AccountInfo,
+        mint: &Pubkey,
+        collection: &Pubkey,
+    ) -> ProgramResult {
+        let (metadata_pda, _) = Pubkey::find_program_address(
+            &[b"metadata", token_metadata::id().as_ref(), mint.as_ref()],
+            &token_metadata::id(),
+        );
+
+        assert_true(
+            *metadata_account.owner == token_metadata::id()
+                && metadata_pda == *metadata_account.key,
+            ProgramError::from(PollError::PdaNotMatched),
+            "Metadata account does not match the nft",
+        )?;
+
+        let metadata = NftMetadata::unpack(&metadata_account.try_borrow_data()?)?;
+
+        assert_true(
+            metadata.mint == *mint && metadata.verified_collection() == Some(*collection),
+            ProgramError::from(PollError::NftNotInCollection),
+            "Nft is not a verified member of the poll's collection",
+        )
+    }
+
+    //move the whole token account into the voter's escrow for the poll,
+    //the escrow is a token account that is its own authority,
+    //returns everything escrowed so far
+    fn lock_tokens<'a>(
+        accounts_iter: &mut Iter<AccountInfo<'a>>,
+        voter: &AccountInfo<'a>,
+        token_account: &AccountInfo<'a>,
+        system_program: &AccountInfo<'a>,
+        mint: &Pubkey,
+        voter_seeds: &[Vec<u8>],
+        _program_id: &Pubkey,
+    ) -> Result<u64, ProgramError> {
+        //vote mint
+        let mint_account_iter = next_account_info(accounts_iter)?;
+        //escrow account
+        let escrow_account_iter = next_account_info(accounts_iter)?;
+        //token program
+        let token_program_iter = next_account_info(accounts_iter)?;
+        //rent sysvar
+        let rent_sysvar_iter = next_account_info(accounts_iter)?;
+
+        assert_true(
+            *mint_account_iter.key == *mint,
+            ProgramError::from(PollError::TokenAccountMismatch),
+            "Mint is not the poll's vote mint",
+        )?;
+
+        let mut seeds = escrow_seeds(voter_seeds);
+        let (pda, bump) = Pubkey::find_program_address(&seed_refs(&seeds), _program_id);
+        seeds.push(vec![bump]);
+
+        assert_true(
+            pda == *escrow_account_iter.key,
+            ProgramError::from(PollError::PdaNotMatched),
+            "Escrow pda does not match",
+        )?;
+
+        if escrow_account_iter.data_is_empty() {
+            invoke_signed(
+                &system_instruction::create_account(
+                    voter.key,
+                    escrow_account_iter.key,
+                    Rent::get()?.minimum_balance(TokenAccount::LEN),
+                    TokenAccount::LEN as u64,
+                    &spl_token::id(),
+                ),
+                &[
+                    voter.clone(),
+                    escrow_account_iter.clone(),
+                    system_program.clone(),
+                ],
+                &[&seed_refs(&seeds)],
+            )?;
+            invoke(
+                &spl_token::instruction::initialize_account(
+                    &spl_token::id(),
+                    escrow_account_iter.key,
+                    mint_account_iter.key,
+                    escrow_account_iter.key,
+                )?,
+                &[
+                    escrow_account_iter.clone(),
+                    mint_account_iter.clone(),
+                    escrow_account_iter.clone(),
+                    rent_sysvar_iter.clone(),
+                    token_program_iter.clone(),
+                ],
+            )?;
+        }
+
+        //revotes can add tokens, or use what is already escrowed
+        let amount = TokenAccount::unpack(&token_account.try_borrow_data()?)?.amount;
+        if amount > 0 {
+            invoke(
+                &spl_token::instruction::transfer(
+                    &spl_token::id(),
+                    token_account.key,
+                    escrow_account_iter.key,
+                    voter.key,
+                    &[],
+                    amount,
+                )?,
+                &[
+                    token_account.clone(),
+                    escrow_account_iter.clone(),
+                    voter.clone(),
+                    token_program_iter.clone(),
+                ],
+            )?;
+        }
+
+        let escrow = TokenAccount::unpack(&escrow_account_iter.try_borrow_data()?)?;
+        assert_true(
+            escrow.mint == *mint && escrow.owner == *escrow_account_iter.key,
+            ProgramError::from(PollError::TokenAccountMismatch),
+            "Escrow does not hold the poll's vote mint",
+        )?;
+
+        Ok(escrow.amount)
+    }
+
+    fn withdraw(_accounts: &[AccountInfo], poll_id: u64, _program_id: &Pubkey) -> ProgramResult {
+        let accounts_iter = &mut _accounts.iter();
+        //poll pda
+        let poll_pda_account_iter = next_account_info(accounts_iter)?;
+        //escrow account
+        let escrow_account_iter = next_account_info(accounts_iter)?;
+        //token account receiving the tokens
+        let token_account_iter = next_account_info(accounts_iter)?;
+        //voter account
+        let voter_iter = next_account_info(accounts_iter)?;
+        //creator account
+        let creator_iter = next_account_info(accounts_iter)?;
+        //token program
+        let token_program_iter = next_account_info(accounts_iter)?;
+
+        assert_true(
+            voter_iter.is_signer,
+            ProgramError::from(PollError::VoterNotSigner),
+            "Voter has to sign",
+        )?;
+
+        let poll_seeds = if poll_pda_account_iter.data_is_empty() {
+            //poll was already deleted by its creator, voting is over
+            let mut poll_seeds = poll_seeds(creator_iter.key, poll_id);
+            let (pda, bump) = Pubkey::find_program_address(&seed_refs(&poll_seeds), _program_id);
+            assert_true(
+                pda == *poll_pda_account_iter.key,
+                ProgramError::from(PollError::PdaNotMatched),
+                "Pda does not match",
+            )?;
+            poll_seeds.push(vec![bump]);
+            poll_seeds
+        } else {
+            let (poll_pda, poll_seeds) =
+                Self::load_poll(poll_pda_account_iter, poll_id, _program_id)?;
+            assert_voting_over(&poll_pda)?;
+            poll_seeds
+        };
+
+        let mut voter_seeds = poll_seeds;
+        voter_seeds.push(voter_iter.key.to_bytes().to_vec());
+        let mut seeds = escrow_seeds(&voter_seeds);
+        let (pda, bump) = Pubkey::find_program_address(&seed_refs(&seeds), _program_id);
+        seeds.push(vec![bump]);
+
+        assert_true(
+            pda == *escrow_account_iter.key,
+            ProgramError::from(PollError::PdaNotMatched),
+            "Escrow pda does not match",
+        )?;
+
+        let escrow = TokenAccount::unpack(&escrow_account_iter.try_borrow_data()?)?;
+        invoke_signed(
+            &spl_token::instruction::transfer(
+                &spl_token::id(),
+                escrow_account_iter.key,
+                token_account_iter.key,
+                escrow_account_iter.key,
+                &[],
+                escrow.amount,
+            )?,
+            &[
+                escrow_account_iter.clone(),
+                token_account_iter.clone(),
+                escrow_account_iter.clone(),
+                token_program_iter.clone(),
+            ],
+            &[&seed_refs(&seeds)],
+        )?;
+        invoke_signed(
+            &spl_token::instruction::close_account(
+                &spl_token::id(),
+                escrow_account_iter.key,
+                voter_iter.key,
+                escrow_account_iter.key,
+                &[],
+            )?,
+            &[
+                escrow_account_iter.clone(),
+                voter_iter.clone(),
+                escrow_account_iter.clone(),
+                token_program_iter.clone(),
+            ],
+            &[&seed_refs(&seeds)],
+        )?;
+
+        msg!("withdrew {} token(s) from poll {}", escrow.amount, poll_id);
+        Ok(())
+    }
+
+    //read the ballot box of a ranked choice poll (poll seeds include bump)
+    fn load_ballot_box(
+        ballot_box_account: &AccountInfo,
+        poll_seeds: &[Vec<u8>],
+        _program_id: &Pubkey,
+    ) -> Result<BallotBox, ProgramError> {
+        let ballot_box = BallotBox::unpack_unchecked(&ballot_box_account.try_borrow_data()?)?;
+
+        assert_true(
+            ballot_box.is_initialized(),
+            ProgramError::from(PollError::NotInitialized),
+            "Ballot box is not initialized",
+        )?;
+
+        let mut seeds = ballot_box_seeds(poll_seeds);
+        seeds.push(vec![ballot_box.bump]);
+        let pda = Pubkey::create_program_address(&seed_refs(&seeds), _program_id)?;
+
+        assert_true(
+            pda == *ballot_box_account.key,
+            ProgramError::from(PollError::PdaNotMatched),
+            "Ballot box pda does not match",
+        )?;
+
+        Ok(ballot_box)
+    }
+
+    //move all lamports to the receiver and wipe the data,
+    //the runtime deletes the account at the end of the transaction
+    fn close_account(account: &AccountInfo, receiver: &AccountInfo) -> ProgramResult {
+        let lamports = account.lamports();
+        **receiver.try_borrow_mut_lamports()? = receiver
+            .lamports()
+            .checked_add(lamports)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        **account.try_borrow_mut_lamports()? = 0;
+        account.try_borrow_mut_data()?.fill(0);
+        Ok(())
+    }
+
+    //read the voter pda of a voter in a poll (poll seeds include bump),
+    //the voter has to have voted
+    fn load_voter(
+        voter_account: &AccountInfo,
+        voter: &Pubkey,
+        poll_seeds: Vec<Vec<u8>>,
+        _program_id: &Pubkey,
+    ) -> Result<PollVoter, ProgramError> {
+        let mut voter_seeds = poll_seeds;
+        voter_seeds.push(voter.to_bytes().to_vec());
+        let (voter_pda, _) = Pubkey::find_program_address(&seed_refs(&voter_seeds), _program_id);
+
+        assert_true(
+            voter_pda == *voter_account.key,
+            ProgramError::from(PollError::PdaNotMatched),
+            "Pda does not match",
+        )?;
+
+        let voter_pda = if voter_account.data_len() == PollVoter::LEGACY_SIZE {
+            PollVoter::unpack_legacy(&voter_account.try_borrow_data()?)?
+        } else {
+            PollVoter::unpack_unchecked(&voter_account.try_borrow_data()?)?
+        };
+
+        assert_true(
+            voter_pda.is_initialized(),
+            ProgramError::from(PollError::VoteNotInitialized),
+            "Voter has not voted",
+        )?;
+
+        Ok(voter_pda)
+    }
+
+    //read a poll and check it is the pda for poll_id,
+    //returns the poll with its seeds (including bump)
+    fn load_poll(
+        poll_account: &AccountInfo,
+        poll_id: u64,
+        _program_id: &Pubkey,
+    ) -> Result<(Poll, Vec<Vec<u8>>), ProgramError> {
+        assert_true(
+            poll_account.data_len() != Poll::LEGACY_SIZE,
+            ProgramError::from(PollError::PollNotMigrated),
+            "Poll has to be migrated first",
+        )?;
+
+        let poll = Poll::unpack_unchecked(&poll_account.try_borrow_data()?)?;
+
+        assert_true(
+            poll.is_initialized(),
+            ProgramError::from(PollError::NotInitialized),
+            "Poll is not initialized",
+        )?;
+
+        let mut poll_seeds = poll_seeds(&poll.creator, poll.id);
+        poll_seeds.push(vec![poll.bump]);
+
+        //get poll account
+        let poll_pda_account =
+            Pubkey::create_program_address(&seed_refs(&poll_seeds), _program_id)?;
+
+        assert_true(
+            poll_pda_account == *poll_account.key,
+            ProgramError::from(PollError::PdaNotMatched),
+            "Pda does not match",
+        )?;
+
+        assert_true(
+            poll.id == poll_id,
+            ProgramError::from(PollError::PollMismatch),
+            "Poll account does not match",
+        )?;
+
+        Ok((poll, poll_seeds))
+    }
+
+    fn migrate_poll(_accounts: &[AccountInfo], poll_id: u8, _program_id: &Pubkey) -> ProgramResult {
+        let accounts_iter = &mut _accounts.iter();
+        //poll pda
+        let poll_pda_account_iter = next_account_info(accounts_iter)?;
+        //system program
+        let system_program_account = next_account_info(accounts_iter)?;
+        //payer account
+        let payer_account_iter = next_account_info(accounts_iter)?;
+
+        assert_true(
+            payer_account_iter.is_signer,
+            ProgramError::MissingRequiredSignature,
+            "Payer has to sign the migration",
+        )?;
+
+        assert_true(
+            poll_pda_account_iter.data_len() == Poll::LEGACY_SIZE,
+            ProgramError::from(PollError::PollAlreadyMigrated),
+            "Poll is already migrated",
+        )?;
+
+        let poll_pda = Poll::unpack_legacy(&poll_pda_account_iter.try_borrow_data()?)?;
+
+        assert_true(
+            poll_pda.is_initialized() && poll_pda.id == poll_id as u64,
+            ProgramError::from(PollError::PollMismatch),
+            "Poll account does not match",
+        )?;
+
+        let poll_pda_account = Pubkey::create_program_address(
+            &[POLL_SEED, &[poll_id], &[poll_pda.bump]],
+            _program_id,
+        )?;
+
+        assert_true(
+            poll_pda_account == *poll_pda_account_iter.key,
+            ProgramError::from(PollError::PdaNotMatched),
+            "Pda does not match",
+        )?;
+
+        Self::resize_account(
+            poll_pda_account_iter,
+            payer_account_iter,
+            system_program_account,
+            Poll::size(poll_pda.options_count),
+        )?;
+        Poll::pack(poll_pda, &mut poll_pda_account_iter.try_borrow_mut_data()?)?;
+
+        Ok(())
+    }
+
+    fn add_proposal_instruction(
+        _accounts: &[AccountInfo],
+        poll_id: u64,
+        instruction: ProposalInstruction,
+        _program_id: &Pubkey,
+    ) -> ProgramResult {
+        let accounts_iter = &mut _accounts.iter();
+        //poll pda
+        let poll_pda_account_iter = next_account_info(accounts_iter)?;
+        //proposal pda
+        let proposal_account_iter = next_account_info(accounts_iter)?;
+        //creator account
+        let creator_iter = next_account_info(accounts_iter)?;
+        //system program
+        let system_program_account = next_account_info(accounts_iter)?;
+        //governance config pda
+        let governance_config_iter = next_account_info(accounts_iter)?;
+
+        let (mut poll_pda, poll_seeds) =
+            Self::load_poll(poll_pda_account_iter, poll_id, _program_id)?;
+
+        assert_creator(&poll_pda, creator_iter)?;
+
+        //voting starts after this, the whole window counts
+        let governance_config =
+            Self::load_governance_config(governance_config_iter, creator_iter.key, _program_id)?;
+        assert_governance_rules(
+            &governance_config,
+            poll_pda.quorum,
+            poll_pda.threshold,
+            poll_pda.end_ts.saturating_sub(poll_pda.start_ts),
+        )?;
+
+        //voters have to see every instruction before they can vote
+        assert_true(
+            poll_pda.status == PollStatus::Open
+                && poll_pda.start_ts != 0
+                && Clock::get()?.unix_timestamp < poll_pda.start_ts,
+            ProgramError::from(PollError::ProposalLocked),
+            "Instructions can only be added before voting starts",
+        )?;
+
+        assert_true(
+            poll_pda.spend_amount == 0,
+            ProgramError::from(PollError::InvalidSpend),
+            "Spend polls can not have instructions",
+        )?;
+
+        //voters need the whole voting window, executable polls can not be
+        //closed before it ends
+        assert_true(
+            poll_pda.end_ts != 0,
+            ProgramError::from(PollError::InvalidVotingWindow),
+            "Executable polls need a voting end",
+        )?;
+
+        let mut seeds = proposal_seeds(&poll_seeds);
+        let (pda, bump) = Pubkey::find_program_address(&seed_refs(&seeds), _program_id);
+        seeds.push(vec![bump]);
+
+        assert_true(
+            pda == *proposal_account_iter.key,
+            ProgramError::from(PollError::PdaNotMatched),
+            "Proposal pda does not match",
+        )?;
+
+        let mut proposal = if proposal_account_iter.data_is_empty() {
+            let proposal = Proposal {
+                is_initialized: true,
+                poll_id,
+                bump,
+                instructions: Vec::new(),
+            };
+            let size = proposal.size();
+            invoke_signed(
+                &system_instruction::create_account(
+                    creator_iter.key,
+                    proposal_account_iter.key,
+                    Rent::get()?.minimum_balance(size),
+                    size as u64,
+                    _program_id,
+                ),
+                &[
+                    creator_iter.clone(),
+                    proposal_account_iter.clone(),
+                    system_program_account.clone(),
+                ],
+                &[&seed_refs(&seeds)],
+            )?;
+            proposal
+        } else {
+            Self::load_proposal(proposal_account_iter, &poll_seeds, _program_id)?
+        };
+
+        msg!(
+            "proposal {} instruction {}: program {}",
+            poll_id,
+            proposal.instructions.len(),
+            instruction.program_id
+        );
+        proposal.instructions.push(instruction);
+        Self::resize_account(
+            proposal_account_iter,
+            creator_iter,
+            system_program_account,
+            proposal.size(),
+        )?;
+        Proposal::pack(proposal, &mut proposal_account_iter.try_borrow_mut_data()?)?;
+
+        poll_pda.proposal_state = ProposalState::Pending;
+        Poll::pack(poll_pda, &mut poll_pda_account_iter.try_borrow_mut_data()?)
+    }
+
+    fn execute_proposal(
+        _accounts: &[AccountInfo],
+        poll_id: u64,
+        _program_id: &Pubkey,
+    ) -> ProgramResult {
+        let accounts_iter = &mut _accounts.iter();
+        //poll pda
+        let poll_pda_account_iter = next_account_info(accounts_iter)?;
+        //proposal pda
+        let proposal_account_iter = next_account_info(accounts_iter)?;
+        //governance pda, followed by the programs and accounts of the instructions
+        let governance_account_iter = next_account_info(accounts_iter)?;
+
+        let (mut poll_pda, poll_seeds) =
+            Self::load_poll(poll_pda_account_iter, poll_id, _program_id)?;
+
+        assert_proposal_queued(&poll_pda)?;
+
+        assert_true(
+            Clock::get()?.unix_timestamp >= poll_pda.execute_after,
+            ProgramError::from(PollError::ProposalTimelocked),
+            "Proposal is timelocked",
+        )?;
+
+        let proposal = Self::load_proposal(proposal_account_iter, &poll_seeds, _program_id)?;
+
+        let mut seeds = governance_seeds(&poll_pda.creator);
+        let (pda, bump) = Pubkey::find_program_address(&seed_refs(&seeds), _program_id);
+        seeds.push(vec![bump]);
+
+        assert_true(
+            pda == *governance_account_iter.key,
+            ProgramError::from(PollError::PdaNotMatched),
+            "Governance pda does not match",
+        )?;
+
+        //a proposal runs once, even if an instruction calls back into the program
+        poll_pda.proposal_state = ProposalState::Executed;
+        Poll::pack(poll_pda, &mut poll_pda_account_iter.try_borrow_mut_data()?)?;
+
+        for (i, instruction) in proposal.instructions.iter().enumerate() {
+            msg!(
+                "proposal {} instruction {}: program {}",
+                poll_id,
+                i,
+                instruction.program_id
+            );
+            invoke_signed(&instruction.instruction(), _accounts, &[&seed_refs(&seeds)])?;
+        }
+
+        Ok(())
+    }
+
+    fn cancel_proposal(
+        _accounts: &[AccountInfo],
+        poll_id: u64,
+        _program_id: &Pubkey,
+    ) -> ProgramResult {
+        let accounts_iter = &mut _accounts.iter();
+        //poll pda
+        let poll_pda_account_iter = next_account_info(accounts_iter)?;
+        //creator or council account
+        let authority_iter = next_account_info(accounts_iter)?;
+
+        let (mut poll_pda, _) = Self::load_poll(poll_pda_account_iter, poll_id, _program_id)?;
+
+        assert_true(
+            authority_iter.is_signer
+                && (poll_pda.creator == *authority_iter.key
+                    || (poll_pda.council != Pubkey::default()
+                        && poll_pda.council == *authority_iter.key)),
+            ProgramError::from(PollError::Unauthorized),
+            "Only the poll creator or council can cancel",
+        )?;
+
+        assert_proposal_queued(&poll_pda)?;
+
+        assert_true(
+            Clock::get()?.unix_timestamp < poll_pda.execute_after,
+            ProgramError::from(PollError::TimelockOver),
+            "Proposal can only be cancelled while timelocked",
+        )?;
+
+        poll_pda.proposal_state = ProposalState::Cancelled;
+        msg!("proposal {} cancelled by {}", poll_id, authority_iter.key);
+        Poll::pack(poll_pda, &mut poll_pda_account_iter.try_borrow_mut_data()?)
+    }
+
+    fn fund_treasury(
+        _accounts: &[AccountInfo],
+        creator: &Pubkey,
+        mint: &Pubkey,
+        amount: u64,
+        _program_id: &Pubkey,
+    ) -> ProgramResult {
+        let accounts_iter = &mut _accounts.iter();
+        //treasury pda
+        let treasury_account_iter = next_account_info(accounts_iter)?;
+        //funder account
+        let funder_iter = next_account_info(accounts_iter)?;
+        //system program
+        let system_program_account = next_account_info(accounts_iter)?;
+
+        let (pda, _) =
+            Pubkey::find_program_address(&seed_refs(&treasury_seeds(creator)), _program_id);
+
+        assert_true(
+            pda == *treasury_account_iter.key,
+            ProgramError::from(PollError::PdaNotMatched),
+            "Treasury pda does not match",
+        )?;
+
+        if *mint == Pubkey::default() {
+            invoke(
+                &system_instruction::transfer(funder_iter.key, treasury_account_iter.key, amount),
+                &[
+                    funder_iter.clone(),
+                    treasury_account_iter.clone(),
+                    system_program_account.clone(),
+                ],
+            )?;
+            msg!("treasury of {} funded with {} lamport(s)", creator, amount);
+            return Ok(());
+        }
+
+        //funder token account
+        let token_account_iter = next_account_info(accounts_iter)?;
+        //mint
+        let mint_account_iter = next_account_info(accounts_iter)?;
+        //treasury token account
+        let treasury_token_account_iter = next_account_info(accounts_iter)?;
+        //token program
+        let token_program_iter = next_account_info(accounts_iter)?;
+        //rent sysvar
+        let rent_sysvar_iter = next_account_info(accounts_iter)?;
+
+        assert_true(
+            *mint_account_iter.key == *mint,
+            ProgramError::from(PollError::TokenAccountMismatch),
+            "Mint does not match",
+        )?;
+
+        let mut seeds = treasury_token_seeds(creator, mint);
+        let (pda, bump) = Pubkey::find_program_address(&seed_refs(&seeds), _program_id);
+        seeds.push(vec![bump]);
+
+        assert_true(
+            pda == *treasury_token_account_iter.key,
+            ProgramError::from(PollError::PdaNotMatched),
+            "Treasury token pda does not match",
+        )?;
+
+        if treasury_token_account_iter.data_is_empty() {
+            invoke_signed(
+                &system_instruction::create_account(
+                    funder_iter.key,
+                    treasury_token_account_iter.key,
+                    Rent::get()?.minimum_balance(TokenAccount::LEN),
+                    TokenAccount::LEN as u64,
+                    &spl_token::id(),
+                ),
+                &[
+                    funder_iter.clone(),
+                    treasury_token_account_iter.clone(),
+                    system_program_account.clone(),
+                ],
+                &[&seed_refs(&seeds)],
+            )?;
+            invoke(
+                &spl_token::instruction::initialize_account(
+                    &spl_token::id(),
+                    treasury_token_account_iter.key,
+                    mint_account_iter.key,
+                    treasury_account_iter.key,
+                )?,
+                &[
+                    treasury_token_account_iter.clone(),
+                    mint_account_iter.clone(),
+                    treasury_account_iter.clone(),
+                    rent_sysvar_iter.clone(),
+                    token_program_iter.clone(),
+                ],
+            )?;
+        }
+
+        let treasury_token = TokenAccount::unpack(&treasury_token_account_iter.try_borrow_data()?)?;
+        assert_true(
+            treasury_token.mint == *mint && treasury_token.owner == *treasury_account_iter.key,
+            ProgramError::from(PollError::TokenAccountMismatch),
+            "Treasury token account does not hold the mint",
+        )?;
+
+        invoke(
+            &spl_token::instruction::transfer(
+                &spl_token::id(),
+                token_account_iter.key,
+                treasury_token_account_iter.key,
+                funder_iter.key,
+                &[],
+                amount,
+            )?,
+            &[
+                token_account_iter.clone(),
+                treasury_token_account_iter.clone(),
+                funder_iter.clone(),
+                token_program_iter.clone(),
+            ],
+        )?;
+        msg!(
+            "treasury of {} funded with {} token(s) of {}",
+            creator,
+            amount,
+            mint
+        );
+        Ok(())
+    }
+
+    fn execute_spend(
+        _accounts: &[AccountInfo],
+        poll_id: u64,
+        _program_id: &Pubkey,
+    ) -> ProgramResult {
+        let accounts_iter = &mut _accounts.iter();
+        //poll pda
+        let poll_pda_account_iter = next_account_info(accounts_iter)?;
+        //treasury pda
+        let treasury_account_iter = next_account_info(accounts_iter)?;
+        //recipient account, or the recipient's token account
+        let recipient_account_iter = next_account_info(accounts_iter)?;
+        //system program
+        let system_program_account = next_account_info(accounts_iter)?;
+
+        let (mut poll_pda, _) = Self::load_poll(poll_pda_account_iter, poll_id, _program_id)?;
+
+        assert_true(
+            poll_pda.spend_amount > 0,
+            ProgramError::from(PollError::InvalidSpend),
+            "Poll is not a spend poll",
+        )?;
+
+        assert_proposal_queued(&poll_pda)?;
+
+        assert_true(
+            Clock::get()?.unix_timestamp >= poll_pda.execute_after,
+            ProgramError::from(PollError::ProposalTimelocked),
+            "Spend is timelocked",
+        )?;
+
+        let creator = poll_pda.creator;
+        let recipient = poll_pda.spend_recipient;
+        let mint = poll_pda.spend_mint;
+        let amount = poll_pda.spend_amount;
+
+        let mut seeds = treasury_seeds(&creator);
+        let (pda, bump) = Pubkey::find_program_address(&seed_refs(&seeds), _program_id);
+        seeds.push(vec![bump]);
+
+        assert_true(
+            pda == *treasury_account_iter.key,
+            ProgramError::from(PollError::PdaNotMatched),
+            "Treasury pda does not match",
+        )?;
+
+        //a spend is paid once
+        poll_pda.proposal_state = ProposalState::Executed;
+        Poll::pack(poll_pda, &mut poll_pda_account_iter.try_borrow_mut_data()?)?;
+
+        if mint == Pubkey::default() {
+            assert_true(
+                *recipient_account_iter.key == recipient,
+                ProgramError::from(PollError::InvalidSpend),
+                "Recipient does not match",
+            )?;
+            invoke_signed(
+                &system_instruction::transfer(
+                    treasury_account_iter.key,
+                    recipient_account_iter.key,
+                    amount,
+                ),
+                &[
+                    treasury_account_iter.clone(),
+                    recipient_account_iter.clone(),
+                    system_program_account.clone(),
+                ],
+                &[&seed_refs(&seeds)],
+            )?;
+            msg!(
+                "poll {} spent {} lamport(s) to {}",
+                poll_id,
+                amount,
+                recipient
+            );
+            return Ok(());
+        }
+
+        //treasury token account
+        let treasury_token_account_iter = next_account_info(accounts_iter)?;
+        //token program
+        let token_program_iter = next_account_info(accounts_iter)?;
+
+        let (pda, _) = Pubkey::find_program_address(
+            &seed_refs(&treasury_token_seeds(&creator, &mint)),
+            _program_id,
+        );
+
+        assert_true(
+            pda == *treasury_token_account_iter.key,
+            ProgramError::from(PollError::PdaNotMatched),
+            "Treasury token pda does not match",
+        )?;
+
+        Self::load_token_account(recipient_account_iter, &recipient, &mint)?;
+
+        invoke_signed(
+            &spl_token::instruction::transfer(
+                &spl_token::id(),
+                treasury_token_account_iter.key,
+                recipient_account_iter.key,
+                treasury_account_iter.key,
+                &[],
+                amount,
+            )?,
+            &[
+                treasury_token_account_iter.clone(),
+                recipient_account_iter.clone(),
+                treasury_account_iter.clone(),
+                token_program_iter.clone(),
+            ],
+            &[&seed_refs(&seeds)],
+        )?;
+        msg!(
+            "poll {} spent {} token(s) of {} to {}",
+            poll_id,
+            amount,
+            mint,
+            recipient
+        );
+        Ok(())
+    }
+
+    fn veto_poll(_accounts: &[AccountInfo], poll_id: u64, _program_id: &Pubkey) -> ProgramResult {
+        let accounts_iter = &mut _accounts.iter();
+        //poll pda
+        let poll_pda_account_iter = next_account_info(accounts_iter)?;
+
+        let (mut poll_pda, _) = Self::load_poll(poll_pda_account_iter, poll_id, _program_id)?;
+
+        assert_true(
+            poll_pda.veto_threshold > 0,
+            ProgramError::from(PollError::InvalidVetoAuthority),
+            "Poll has no veto authority",
+        )?;
+
+        //veto keys signing, each counted once
+        let mut signers: Vec<&Pubkey> = Vec::new();
+        for veto_account in accounts_iter {
+            if veto_account.is_signer
+                && poll_pda.veto_keys.contains(veto_account.key)
+                && !signers.contains(&veto_account.key)
+            {
+                signers.push(veto_account.key);
+            }
+        }
+
+        assert_true(
+            signers.len() >= poll_pda.veto_threshold as usize,
+            ProgramError::from(PollError::Unauthorized),
+            "Not enough veto keys signed",
+        )?;
+
+        //a poll can be vetoed from passing until its proposal or spend runs
+        assert_true(
+            poll_pda.outcome == PollOutcome::Passed,
+            ProgramError::from(PollError::ProposalNotPassed),
+            "Only passed polls can be vetoed",
+        )?;
+
+        assert_true(
+            poll_pda.proposal_state != ProposalState::Executed,
+            ProgramError::from(PollError::ProposalAlreadyExecuted),
+            "Proposal was already executed",
+        )?;
+
+        poll_pda.outcome = PollOutcome::Vetoed;
+        if poll_pda.proposal_state != ProposalState::None {
+            poll_pda.proposal_state = ProposalState::Cancelled;
+        }
+        msg!("poll {} vetoed by {} key(s)", poll_id, signers.len());
+        Poll::pack(poll_pda, &mut poll_pda_account_iter.try_borrow_mut_data()?)
+    }
+
+    //read an executable poll's proposal, poll seeds include the poll's bump
+    fn load_proposal(
+        proposal_account: &AccountInfo,
+        poll_seeds: &[Vec<u8>],
+        _program_id: &Pubkey,
+    ) -> Result<Proposal, ProgramError> {
+        assert_true(
+            proposal_account.owner == _program_id,
+            ProgramError::from(PollError::NotInitialized),
+            "Proposal is not owned by the program",
+        )?;
+
+        let proposal = Proposal::unpack(&proposal_account.try_borrow_data()?)?;
+
+        let mut seeds = proposal_seeds(poll_seeds);
+        seeds.push(vec![proposal.bump]);
+        let pda = Pubkey::create_program_address(&seed_refs(&seeds), _program_id)?;
+
+        assert_true(
+            pda == *proposal_account.key,
+            ProgramError::from(PollError::PdaNotMatched),
+            "Proposal pda does not match",
+        )?;
+
+        Ok(proposal)
+    }
+
+    fn set_governance_config(
+        _accounts: &[AccountInfo],
+        min_quorum: u64,
+        min_threshold: u16,
+        min_voting_period: i64,
+        _program_id: &Pubkey,
+    ) -> ProgramResult {
+        let accounts_iter = &mut _accounts.iter();
+        //governance config pda
+        let governance_config_iter = next_account_info(accounts_iter)?;
+        //creator account
+        let creator_iter = next_account_info(accounts_iter)?;
+        //system program
+        let system_program_account = next_account_info(accounts_iter)?;
+
+        assert_true(
+            creator_iter.is_signer,
+            ProgramError::from(PollError::Unauthorized),
+            "Creator has to sign",
+        )?;
+
+        //the config binds every later poll, it can not be loosened
+        assert_true(
+            governance_config_iter.data_is_empty(),
+            ProgramError::from(PollError::GovernanceConfigSet),
+            "Governance config is already set",
+        )?;
+
+        assert_true(
+            min_quorum > 0 && min_threshold <= 10_000,
+            ProgramError::from(PollError::InvalidInstrucitonData),
+            "Governance needs a quorum, threshold is at most 10000",
+        )?;
+
+        assert_true(
+            min_voting_period > 0,
+            ProgramError::from(PollError::InvalidVotingWindow),
+            "Governance needs a voting period",
+        )?;
+
+        let mut seeds = governance_config_seeds(creator_iter.key);
+        let (pda, bump) = Pubkey::find_program_address(&seed_refs(&seeds), _program_id);
+        seeds.push(vec![bump]);
+
+        assert_true(
+            pda == *governance_config_iter.key,
+            ProgramError::from(PollError::PdaNotMatched),
+            "Governance config pda does not match",
+        )?;
+
+        invoke_signed(
+            &system_instruction::create_account(
+                creator_iter.key,
+                governance_config_iter.key,
+                Rent::get()?.minimum_balance(GovernanceConfig::SIZE),
+                GovernanceConfig::SIZE as u64,
+                _program_id,
+            ),
+            &[
+                creator_iter.clone(),
+                governance_config_iter.clone(),
+                system_program_account.clone(),
+            ],
+            &[&seed_refs(&seeds)],
+        )?;
+
+        let governance_config = GovernanceConfig {
+            is_initialized: true,
+            creator: *creator_iter.key,
+            bump,
+            min_quorum,
+            min_threshold,
+            min_voting_period,
+        };
+        GovernanceConfig::pack(
+            governance_config,
+            &mut governance_config_iter.try_borrow_mut_data()?,
+        )?;
+
+        msg!(
+            "{} governance: quorum {}, threshold {}, voting period {}",
+            creator_iter.key,
+            min_quorum,
+            min_threshold,
+            min_voting_period
+        );
+        Ok(())
+    }
+
+    fn load_governance_config(
+        governance_config_account: &AccountInfo,
+        creator: &Pubkey,
+        _program_id: &Pubkey,
+    ) -> Result<GovernanceConfig, ProgramError> {
+        assert_true(
+            governance_config_account.owner == _program_id,
+            ProgramError::from(PollError::GovernanceConfigNotSet),
+            "Creator has no governance config",
+        )?;
+
+        let governance_config =
+            GovernanceConfig::unpack(&governance_config_account.try_borrow_data()?)?;
+
+        let mut seeds = governance_config_seeds(creator);
+        seeds.push(vec![governance_config.bump]);
+        let pda = Pubkey::create_program_address(&seed_refs(&seeds), _program_id)?;
+
+        assert_true(
+            pda == *governance_config_account.key && governance_config.creator == *creator,
+            ProgramError::from(PollError::PdaNotMatched),
+            "Governance config pda does not match",
+        )?;
+
+        Ok(governance_config)
+    }
+
+    //resize a program owned account, payer tops up the rent
+    fn resize_account<'a>(
+        account: &AccountInfo<'a>,
+        payer: &AccountInfo<'a>,
+        system_program: &AccountInfo<'a>,
+        new_len: usize,
+    ) -> ProgramResult {
+        let lamports = Rent::get()?
+            .minimum_balance(new_len)
+            .saturating_sub(account.lamports());
+        if lamports > 0 {
+            invoke(
+                &system_instruction::transfer(payer.key, account.key, lamports),
+                &[payer.clone(), account.clone(), system_program.clone()],
+            )?;
+        }
+        account.realloc(new_len, false)
+    }
 
     // fn init_vote(_accounts: &[AccountInfo], group: u8, _program_id: &Pubkey) -> ProgramResult {
     //     let key: &[u8] = b"vote";
@@ -503,3 +2946,142 @@ impl Processor {
     //     Ok(())
     // }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaf(voter: &Pubkey) -> [u8; 32] {
+        hashv(&[voter.as_ref()]).to_bytes()
+    }
+
+    fn node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+        hashv(&[left, right]).to_bytes()
+    }
+
+    //a single choice poll with two options and committed ballots
+    fn commit_reveal_poll(unrevealed: u64) -> Poll {
+        let options = (0..2)
+            .map(|i| PollOption::new(i, String::new(), 0))
+            .collect();
+        Poll {
+            is_initialized: true,
+            options_count: 2,
+            options,
+            unrevealed,
+            ..Poll::default()
+        }
+    }
+
+    fn committed_voter(option_id: u8, salt: &[u8; 32], weight: u64) -> PollVoter {
+        PollVoter {
+            is_initialized: true,
+            poll_id: 0,
+            option_selected: 0,
+            bump: 0,
+            selections: 0,
+            ballot: 0,
+            weight,
+            commitment: vote_commitment(option_id, salt),
+            payer: Pubkey::default(),
+            allocation: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn revealed_ballot_is_counted_with_its_weight() {
+        let mut poll = commit_reveal_poll(2);
+        let mut voter = committed_voter(2, &[7; 32], 3);
+
+        assert_eq!(reveal_ballot(&mut poll, &mut voter, 2, &[7; 32]), Ok(()));
+        assert_eq!(voter.option_selected, 2);
+        assert_eq!(voter.commitment, [0; 32]);
+        assert_eq!(poll.options[1].votes, 3);
+        assert_eq!(poll.votes_cast, 3);
+        assert_eq!(poll.unrevealed, 1);
+    }
+
+    #[test]
+    fn reveal_with_another_salt_or_option_is_rejected() {
+        let mut poll = commit_reveal_poll(1);
+        let mut voter = committed_voter(1, &[7; 32], 1);
+
+        for (option_id, salt) in [(1, [8; 32]), (2, [7; 32])] {
+            assert_eq!(
+                reveal_ballot(&mut poll, &mut voter, option_id, &salt),
+                Err(ProgramError::from(PollError::CommitmentMismatch))
+            );
+        }
+        assert_eq!(poll.votes_cast, 0);
+        assert_eq!(poll.unrevealed, 1);
+    }
+
+    #[test]
+    fn ballot_is_revealed_only_once() {
+        let mut poll = commit_reveal_poll(2);
+        let mut voter = committed_voter(1, &[7; 32], 1);
+
+        assert_eq!(reveal_ballot(&mut poll, &mut voter, 1, &[7; 32]), Ok(()));
+        assert_eq!(
+            reveal_ballot(&mut poll, &mut voter, 1, &[7; 32]),
+            Err(ProgramError::from(PollError::CommitmentMismatch))
+        );
+        assert_eq!(poll.options[0].votes, 1);
+        assert_eq!(poll.unrevealed, 1);
+    }
+
+    #[test]
+    fn allowlist_pairs_are_hashed_in_sorted_order() {
+        let voters: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+        let leaves: Vec<[u8; 32]> = voters.iter().map(leaf).collect();
+        let sorted = |a: &[u8; 32], b: &[u8; 32]| if a <= b { node(a, b) } else { node(b, a) };
+        let left = sorted(&leaves[0], &leaves[1]);
+        let right = sorted(&leaves[2], &leaves[3]);
+        let root = sorted(&left, &right);
+
+        //proofs carry no directions, every voter reaches the same root
+        assert_eq!(allowlist_root(&voters[0], &[leaves[1], right]), root);
+        assert_eq!(allowlist_root(&voters[1], &[leaves[0], right]), root);
+        assert_eq!(allowlist_root(&voters[2], &[leaves[3], left]), root);
+        assert_eq!(allowlist_root(&voters[3], &[leaves[2], left]), root);
+    }
+
+    #[test]
+    fn single_voter_allowlist_root_is_the_leaf() {
+        let voter = Pubkey::new_unique();
+        let poll = Poll {
+            allowlist_root: leaf(&voter),
+            ..Poll::default()
+        };
+
+        assert_eq!(allowlist_root(&voter, &[]), leaf(&voter));
+        assert_eq!(assert_allowed(&poll, &voter, &[]), Ok(()));
+    }
+
+    #[test]
+    fn wrong_allowlist_proof_is_rejected() {
+        let voters: Vec<Pubkey> = (0..2).map(|_| Pubkey::new_unique()).collect();
+        let outsider = Pubkey::new_unique();
+        let root = allowlist_root(&voters[0], &[leaf(&voters[1])]);
+        let poll = Poll {
+            allowlist_root: root,
+            ..Poll::default()
+        };
+
+        assert_eq!(
+            assert_allowed(&poll, &voters[0], &[leaf(&voters[1])]),
+            Ok(())
+        );
+        for (voter, proof) in [
+            (&voters[0], vec![]),
+            (&voters[0], vec![leaf(&outsider)]),
+            (&voters[0], vec![leaf(&voters[1]), leaf(&voters[1])]),
+            (&outsider, vec![leaf(&voters[1])]),
+        ] {
+            assert_eq!(
+                assert_allowed(&poll, voter, &proof),
+                Err(ProgramError::from(PollError::NotOnAllowlist))
+            );
+        }
+    }
+}
@@ -1,18 +1,64 @@
 use crate::error::PollError;
 use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::instruction::{AccountMeta, Instruction};
 use solana_program::msg;
 use solana_program::program_error::ProgramError;
 use solana_program::program_pack::{IsInitialized, Pack, Sealed};
-//PollCount PDA
+use solana_program::pubkey::Pubkey;
+use std::ops::Range;
+//PollCount PDA, one per creator
 //count
 
 //Polling PDA
-//poll id u8
+//creator pubkey, default for polls from the global namespace
+//  administrative instructions are authorized against it
+//poll id u64
 //poll text str 100
 //poll text len u8
-//options (max 4, len 50)
-//option_count u8
 //bump u8
+//voting start timestamp i64, 0 when polls are open right away
+//voting end timestamp i64, 0 when polls never close
+//allow revote bool, voters can change their vote while the poll is open
+//status u8, open or finalized
+//winner u8, winning option id once finalized, 0 for a tie
+//vote mode u8, single choice, approval or ranked choice
+//max selections u8, most options an approval ballot may select, 0 for no limit
+//vote mint pubkey, votes are weighted by the voter's balance of it,
+//  default for one vote per voter
+//lock tokens bool, voted tokens are held in an escrow until voting is over
+//credits u64, voice credits of every voter on quadratic polls
+//allowlist root [u8; 32], merkle root of the voters allowed to vote,
+//  all 0 when anyone can vote
+//nft collection pubkey, only holders of a verified nft of the collection can
+//  vote, default when polls are not nft gated
+//reveal end timestamp i64, commit-reveal polls take committed ballots until
+//  the voting end and have them revealed until then, 0 for plain ballots
+//unrevealed u64, committed ballots not revealed yet
+//quorum u64, votes that have to be cast for the poll to be decided, 0 for none
+//threshold u16, basis points of the votes cast the pass option has to exceed
+//pass option u8, option id the threshold applies to, 0 for the winner
+//votes cast u64, weight of the ballots counted, each ballot once
+//outcome u8, undecided until finalized, then passed, failed or quorum not met
+//timelock i64, seconds between an executable poll passing and its execution
+//council pubkey, can cancel queued proposals along with the creator,
+//  default for none
+//proposal state u8, none without instructions, pending, queued once passed,
+//  executed or cancelled
+//execute after i64, when a queued proposal can be executed
+//spend recipient pubkey, receives the spend of a spend poll
+//spend mint pubkey, mint of the spent tokens, default for lamports
+//spend amount u64, spent from the creator's treasury once the poll passed,
+//  0 when the poll is not a spend poll
+//veto threshold u8, signatures of veto keys needed to veto the poll once it
+//  passed, 0 without veto authority
+//veto keys (5 pubkeys), default padded
+//allow abstain u8, voters can abstain, counted toward the quorum only
+//allow nota u8, voters can pick none of the above
+//abstain votes u64, weight of the abstaining ballots
+//nota votes u64, weight of the none of the above ballots
+//option_count u8
+//options (2 to 64, len 50), only option_count are stored
 
 // Poll Option PDA
 // option id u8
@@ -21,29 +67,158 @@ use solana_program::program_pack::{IsInitialized, Pack, Sealed};
 // option votes u64
 
 //Voter PDA
-//pollid u8
-//votes u64
-//option u8
+//seeded by the poll's seeds, bump and the voter (the nft mint on nft gated polls)
+//pollid u64
+//option u8, 0 for approval ballots, first choice of ranked ballots
+//bump u8
+//selections u64, bitmask of the selected options (bit 0 is option 1)
+//ballot u32, slot of a ranked ballot in the poll's ballot box
+//weight u64, votes added to each selected option
+//commitment [u8; 32], hash of the option id and a salt on commit-reveal polls,
+//  all 0 once revealed
+//payer pubkey, paid the rent of the pda (a delegate or the voter), refunded
+//  when the pda is closed
+//quadratic polls only: votes u16 per option, the voter's credit allocation
+
+//Delegation PDA
+//seeded by "Delegation", the delegator and the poll pda (default for every poll)
+//delegator pubkey
+//delegate pubkey, votes for the delegator
+//poll pubkey, default when the delegation covers every poll
+//bump u8
+
+//Governance config PDA, set once per creator
+//seeded by "GovernanceConfig" and the creator
+//creator pubkey
+//bump u8
+//min quorum u64, min threshold u16 and min voting period i64 (seconds) of
+//  the creator's executable and spend polls
+
+//Proposal PDA, instructions an executable poll runs once it passes
+//seeded by the poll's seeds, bump and "Proposal", borsh encoded
+//pollid u64
+//bump u8
+//instructions, program id, account metas and data each
+
+//Ballot box PDA, one per ranked choice poll
+//seeded by the poll's seeds, bump and "Ballots"
+//pollid u64
+//bump u8
+//option_count u8
+//ballots count u32
+//tally done bool
+//round u8, instant runoff round being counted
+//cursor u32, next ballot to count in the round
+//eliminated u64, bitmask of the eliminated options
+//round votes u64 per option
+//ballots, weight u64 and option_count bytes each: option ids by
+//  preference, 0 padded, a retracted ballot is all 0
 
 pub const POLL_TITLE_SIZE: usize = 100;
+pub const POLL_VETO_KEYS: usize = 5;
+//built-in choices polls can enable, never ids of poll options
+pub const ABSTAIN_OPTION_ID: u8 = u8::MAX;
+pub const NOTA_OPTION_ID: u8 = u8::MAX - 1;
+
+//metaplex token metadata program, owner of nft metadata accounts
+pub mod token_metadata {
+    solana_program::declare_id!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+}
 pub const POLL_OPTION_SIZE: usize = 50;
 
+//titles are stored space padded to their size in bytes, not in chars
+pub fn pad_text(text: &str, size: usize) -> String {
+    text.to_string() + &" ".repeat(size.saturating_sub(text.len()))
+}
+
 #[derive(Debug, Clone)]
 pub struct PollCount {
     pub is_initialized: bool,
-    pub count: u8,
+    pub count: u64,
     pub bump: u8,
 }
 
 #[derive(Debug, Clone)]
 pub struct Poll {
     pub is_initialized: bool,
-    pub id: u8,
+    pub creator: Pubkey,
+    pub id: u64,
     pub title: String,
     pub title_length: u8,
     pub options: Vec<PollOption>,
     pub options_count: u8,
     pub bump: u8,
+    pub start_ts: i64,
+    pub end_ts: i64,
+    pub allow_revote: bool,
+    pub status: PollStatus,
+    pub winner: u8,
+    pub vote_mode: VoteMode,
+    pub max_selections: u8,
+    pub vote_mint: Pubkey,
+    pub lock_tokens: bool,
+    pub credits: u64,
+    pub allowlist_root: [u8; 32],
+    pub nft_collection: Pubkey,
+    pub reveal_end_ts: i64,
+    pub unrevealed: u64,
+    pub quorum: u64,
+    pub threshold: u16,
+    pub pass_option: u8,
+    pub votes_cast: u64,
+    pub outcome: PollOutcome,
+    pub timelock: i64,
+    pub council: Pubkey,
+    pub proposal_state: ProposalState,
+    pub execute_after: i64,
+    pub spend_recipient: Pubkey,
+    pub spend_mint: Pubkey,
+    pub spend_amount: u64,
+    pub veto_threshold: u8,
+    pub veto_keys: [Pubkey; POLL_VETO_KEYS],
+    pub allow_abstain: bool,
+    pub allow_nota: bool,
+    pub abstain_votes: u64,
+    pub nota_votes: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PollStatus {
+    Open,
+    Finalized,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PollOutcome {
+    Undecided,
+    Passed,
+    Failed,
+    QuorumNotMet,
+    Vetoed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProposalState {
+    //no instructions attached
+    None,
+    //instructions attached, waiting for the poll to pass
+    Pending,
+    //passed, executable once the timelock is over
+    Queued,
+    Executed,
+    Cancelled,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VoteMode {
+    //one option per voter
+    Single,
+    //voters select any number of options (up to max_selections)
+    Approval,
+    //voters rank options, the winner is found by instant runoff
+    Ranked,
+    //voters spread voice credits over options, k votes cost k² credits
+    Quadratic,
 }
 
 #[derive(Debug, Clone)]
@@ -57,13 +232,158 @@ pub struct PollOption {
 #[derive(Debug, Clone)]
 pub struct PollVoter {
     pub is_initialized: bool,
-    pub poll_id: u8,
+    pub poll_id: u64,
     pub option_selected: u8,
     pub bump: u8,
+    pub selections: u64,
+    pub ballot: u32,
+    pub weight: u64,
+    pub commitment: [u8; 32],
+    pub payer: Pubkey,
+    pub allocation: Vec<u16>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Delegation {
+    pub is_initialized: bool,
+    pub delegator: Pubkey,
+    pub delegate: Pubkey,
+    pub poll: Pubkey,
+    pub bump: u8,
+}
+
+#[derive(Debug, Clone)]
+pub struct GovernanceConfig {
+    pub is_initialized: bool,
+    pub creator: Pubkey,
+    pub bump: u8,
+    pub min_quorum: u64,
+    pub min_threshold: u16,
+    pub min_voting_period: i64,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone)]
+pub struct Proposal {
+    pub is_initialized: bool,
+    pub poll_id: u64,
+    pub bump: u8,
+    pub instructions: Vec<ProposalInstruction>,
+}
+
+//instruction of a proposal, invoked with the governance pda as signer
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone, PartialEq, Eq)]
+pub struct ProposalInstruction {
+    pub program_id: Pubkey,
+    pub accounts: Vec<ProposalAccountMeta>,
+    pub data: Vec<u8>,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProposalAccountMeta {
+    pub pubkey: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+//token metadata account of an nft, read up to the collection
+#[derive(BorshDeserialize, Debug, Clone)]
+pub struct NftMetadata {
+    pub key: u8,
+    pub update_authority: Pubkey,
+    pub mint: Pubkey,
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub seller_fee_basis_points: u16,
+    pub creators: Option<Vec<NftCreator>>,
+    pub primary_sale_happened: bool,
+    pub is_mutable: bool,
+    pub edition_nonce: Option<u8>,
+    pub token_standard: Option<u8>,
+    pub collection: Option<NftCollection>,
+}
+
+#[derive(BorshDeserialize, Debug, Clone)]
+pub struct NftCreator {
+    pub address: Pubkey,
+    pub verified: bool,
+    pub share: u8,
+}
+
+#[derive(BorshDeserialize, Debug, Clone)]
+pub struct NftCollection {
+    pub verified: bool,
+    pub key: Pubkey,
+}
+
+#[derive(Debug, Clone)]
+pub struct BallotBox {
+    pub is_initialized: bool,
+    pub poll_id: u64,
+    pub bump: u8,
+    pub options_count: u8,
+    pub ballots_count: u32,
+    pub tally_done: bool,
+    pub round: u8,
+    pub cursor: u32,
+    pub eliminated: u64,
+    pub round_votes: Vec<u64>,
 }
 
 impl PollCount {
-    pub const SIZE: usize = 1 + 1 + 1;
+    pub const SIZE: usize = 1 + 8 + 1;
+}
+
+impl Delegation {
+    pub const SIZE: usize = 1 + 32 + 32 + 32 + 1;
+}
+
+impl GovernanceConfig {
+    pub const SIZE: usize = 1 + 32 + 1 + 8 + 2 + 8;
+}
+
+impl Proposal {
+    //a proposal stores its instructions as they are added, the account grows with them
+    pub fn size(&self) -> usize {
+        self.try_to_vec().map_or(0, |data| data.len())
+    }
+
+    pub fn unpack(src: &[u8]) -> Result<Self, ProgramError> {
+        let proposal =
+            Proposal::deserialize(&mut &src[..]).map_err(|_| ProgramError::InvalidAccountData)?;
+        if !proposal.is_initialized {
+            return Err(ProgramError::UninitializedAccount);
+        }
+        Ok(proposal)
+    }
+
+    pub fn pack(src: Self, dst: &mut [u8]) -> Result<(), ProgramError> {
+        let data = src
+            .try_to_vec()
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+        dst.get_mut(..data.len())
+            .ok_or(ProgramError::AccountDataTooSmall)?
+            .copy_from_slice(&data);
+        Ok(())
+    }
+}
+
+impl ProposalInstruction {
+    pub fn instruction(&self) -> Instruction {
+        Instruction {
+            program_id: self.program_id,
+            accounts: self
+                .accounts
+                .iter()
+                .map(|meta| AccountMeta {
+                    pubkey: meta.pubkey,
+                    is_signer: meta.is_signer,
+                    is_writable: meta.is_writable,
+                })
+                .collect(),
+            data: self.data.clone(),
+        }
+    }
 }
 
 impl PollOption {
@@ -72,7 +392,7 @@ impl PollOption {
     pub fn new(id: u8, title: String, title_length: u8) -> Self {
         PollOption {
             id,
-            title: title,
+            title,
             title_length,
             votes: 0,
         }
@@ -80,25 +400,128 @@ impl PollOption {
 }
 
 impl Poll {
-    pub const OPTIONS_COINT: usize = 4;
+    pub const MIN_OPTIONS: u8 = 2;
+    pub const MAX_OPTIONS: u8 = 64;
+    //polls with a u8 id always stored 4 options
+    pub const LEGACY_OPTIONS_COUNT: usize = 4;
 }
 
 impl Poll {
-    pub const SIZE: usize =
-        1 + 1 + POLL_TITLE_SIZE + 1 + PollOption::SIZE * Poll::OPTIONS_COINT + 1 + 1;
+    //everything before the options
+    pub const HEADER_SIZE: usize = 1
+        + 32
+        + 8
+        + POLL_TITLE_SIZE
+        + 1
+        + 1
+        + 8
+        + 8
+        + 1
+        + 1
+        + 1
+        + 1
+        + 1
+        + 32
+        + 1
+        + 8
+        + 32
+        + 32
+        + 8
+        + 8
+        + 8
+        + 2
+        + 1
+        + 8
+        + 1
+        + 8
+        + 32
+        + 1
+        + 8
+        + 32
+        + 32
+        + 8
+        + 1
+        + 32 * POLL_VETO_KEYS
+        + 1
+        + 1
+        + 8
+        + 8
+        + 1;
+    //size of the account when id was a u8 and polls had no creator
+    pub const LEGACY_SIZE: usize =
+        1 + 1 + POLL_TITLE_SIZE + 1 + PollOption::SIZE * Poll::LEGACY_OPTIONS_COUNT + 1 + 1;
+
+    //polls are told apart from legacy polls by their size,
+    //a poll that would be as big as a legacy one gets a spare byte
+    pub fn size(options_count: u8) -> usize {
+        let size = Poll::HEADER_SIZE + PollOption::SIZE * options_count as usize;
+        if size == Poll::LEGACY_SIZE {
+            size + 1
+        } else {
+            size
+        }
+    }
+
+    //bitmask with a bit for every option of the poll
+    pub fn options_mask(&self) -> u64 {
+        u64::MAX
+            .checked_shr(64 - self.options_count as u32)
+            .unwrap_or(0)
+    }
 }
 
 impl PollVoter {
-    pub const SIZE: usize = 1 + 1 + 1 + 1;
+    //everything before the credit allocation
+    pub const SIZE: usize = 1 + 8 + 1 + 1 + 8 + 4 + 8 + 32 + 32;
+    //size of the account when poll id was a u8
+    pub const LEGACY_SIZE: usize = 1 + 1 + 1 + 1;
+
+    //selections bitmask of a single option id, 0 if it is not a valid id
+    pub fn option_mask(option_id: u8) -> u64 {
+        1u64.checked_shl(option_id.wrapping_sub(1) as u32)
+            .unwrap_or(0)
+    }
+
+    //quadratic voters store their votes for every option of the poll
+    pub fn size(vote_mode: VoteMode, options_count: u8) -> usize {
+        match vote_mode {
+            VoteMode::Quadratic => PollVoter::SIZE + 2 * options_count as usize,
+            _ => PollVoter::SIZE,
+        }
+    }
+
+    //voice credits an allocation costs, k votes on an option cost k²,
+    //None when the cost does not fit in a u64
+    pub fn credits_cost(allocation: &[u16]) -> Option<u64> {
+        allocation.iter().try_fold(0u64, |cost, votes| {
+            (*votes as u64)
+                .checked_mul(*votes as u64)
+                .and_then(|votes_cost| cost.checked_add(votes_cost))
+        })
+    }
+
+    //votes the voter added to the option at index option
+    pub fn option_votes(&self, option: u8) -> u64 {
+        if self.allocation.is_empty() {
+            match self.selections & (1 << option) {
+                0 => 0,
+                _ => self.weight,
+            }
+        } else {
+            self.allocation
+                .get(option as usize)
+                .map_or(0, |votes| (*votes as u64).saturating_mul(self.weight))
+        }
+    }
 }
 
 impl Sealed for PollCount {}
 
-impl Sealed for Poll {}
+impl Sealed for Delegation {}
 
-impl Sealed for PollOption {}
+impl Sealed for GovernanceConfig {}
 
-impl Sealed for PollVoter {}
+impl Sealed for PollOption {}
 
 impl IsInitialized for Poll {
     fn is_initialized(&self) -> bool {
@@ -106,25 +529,159 @@ impl IsInitialized for Poll {
     }
 }
 
+impl IsInitialized for Delegation {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl IsInitialized for GovernanceConfig {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
 impl IsInitialized for PollCount {
     fn is_initialized(&self) -> bool {
         self.is_initialized
     }
 }
 
-impl Pack for Poll {
-    const LEN: usize = Poll::SIZE;
+//an empty poll, new fields get their default here
+impl Default for Poll {
+    fn default() -> Self {
+        Poll {
+            is_initialized: false,
+            creator: Pubkey::default(),
+            id: 0,
+            title: String::new(),
+            title_length: 0,
+            options: Vec::new(),
+            options_count: 0,
+            bump: 0,
+            start_ts: 0,
+            end_ts: 0,
+            allow_revote: false,
+            status: PollStatus::Open,
+            winner: 0,
+            vote_mode: VoteMode::Single,
+            max_selections: 0,
+            vote_mint: Pubkey::default(),
+            lock_tokens: false,
+            credits: 0,
+            allowlist_root: [0; 32],
+            nft_collection: Pubkey::default(),
+            reveal_end_ts: 0,
+            unrevealed: 0,
+            quorum: 0,
+            threshold: 0,
+            pass_option: 0,
+            votes_cast: 0,
+            outcome: PollOutcome::Undecided,
+            timelock: 0,
+            council: Pubkey::default(),
+            proposal_state: ProposalState::None,
+            execute_after: 0,
+            spend_recipient: Pubkey::default(),
+            spend_mint: Pubkey::default(),
+            spend_amount: 0,
+            veto_threshold: 0,
+            veto_keys: [Pubkey::default(); POLL_VETO_KEYS],
+            allow_abstain: false,
+            allow_nota: false,
+            abstain_votes: 0,
+            nota_votes: 0,
+        }
+    }
+}
 
-    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
-        let src = array_ref![src, 0, Poll::LEN];
-        let (is_initialized, id, title, title_length, options, options_count, bump) = array_refs![
-            src,
-            1,
+impl Poll {
+    //options are length prefixed, only the options that exist are read
+    pub fn unpack_unchecked(src: &[u8]) -> Result<Self, ProgramError> {
+        let header = src
+            .get(..Poll::HEADER_SIZE)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        let header = array_ref![header, 0, Poll::HEADER_SIZE];
+        let (
+            is_initialized,
+            creator,
+            id,
+            title,
+            title_length,
+            bump,
+            start_ts,
+            end_ts,
+            allow_revote,
+            status,
+            winner,
+            vote_mode,
+            max_selections,
+            vote_mint,
+            lock_tokens,
+            credits,
+            allowlist_root,
+            nft_collection,
+            reveal_end_ts,
+            unrevealed,
+            quorum,
+            threshold,
+            pass_option,
+            votes_cast,
+            outcome,
+            timelock,
+            council,
+            proposal_state,
+            execute_after,
+            spend_recipient,
+            spend_mint,
+            spend_amount,
+            veto_threshold,
+            veto_keys,
+            allow_abstain,
+            allow_nota,
+            abstain_votes,
+            nota_votes,
+            options_count,
+        ) = array_refs![
+            header,
             1,
+            32,
+            8,
             POLL_TITLE_SIZE,
             1,
-            PollOption::SIZE * Poll::OPTIONS_COINT,
             1,
+            8,
+            8,
+            1,
+            1,
+            1,
+            1,
+            1,
+            32,
+            1,
+            8,
+            32,
+            32,
+            8,
+            8,
+            8,
+            2,
+            1,
+            8,
+            1,
+            8,
+            32,
+            1,
+            8,
+            32,
+            32,
+            8,
+            1,
+            32 * POLL_VETO_KEYS,
+            1,
+            1,
+            8,
+            8,
             1
         ];
         let is_initialized = match is_initialized {
@@ -132,83 +689,331 @@ impl Pack for Poll {
             [1] => true,
             _ => return Err(ProgramError::InvalidAccountData),
         };
+        let allow_revote = match allow_revote {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        let status = match status {
+            [0] => PollStatus::Open,
+            [1] => PollStatus::Finalized,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        let vote_mode = match vote_mode {
+            [0] => VoteMode::Single,
+            [1] => VoteMode::Approval,
+            [2] => VoteMode::Ranked,
+            [3] => VoteMode::Quadratic,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        let lock_tokens = match lock_tokens {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        let outcome = match outcome {
+            [0] => PollOutcome::Undecided,
+            [1] => PollOutcome::Passed,
+            [2] => PollOutcome::Failed,
+            [3] => PollOutcome::QuorumNotMet,
+            [4] => PollOutcome::Vetoed,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        let proposal_state = match proposal_state {
+            [0] => ProposalState::None,
+            [1] => ProposalState::Pending,
+            [2] => ProposalState::Queued,
+            [3] => ProposalState::Executed,
+            [4] => ProposalState::Cancelled,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        let mut keys = [Pubkey::default(); POLL_VETO_KEYS];
+        for (key, src) in keys.iter_mut().zip(veto_keys.chunks_exact(32)) {
+            *key = Pubkey::new(src);
+        }
+        let veto_keys = keys;
+
+        let options_count = u8::from_le_bytes(*options_count);
+        let options = src
+            .get(Poll::HEADER_SIZE..Poll::size(options_count))
+            .ok_or(ProgramError::InvalidAccountData)?;
 
         let mut options_vec = Vec::new();
 
-        for i in 0..u8::from_le_bytes(*options_count) {
-            options_vec.push(Poll::option_at(options, i).unwrap());
+        for i in 0..options_count {
+            options_vec.push(Poll::option_at(options, i).ok_or(ProgramError::InvalidAccountData)?);
         }
 
         Ok(Poll {
             is_initialized,
-            id: u8::from_le_bytes(*id),
+            creator: Pubkey::new_from_array(*creator),
+            id: u64::from_le_bytes(*id),
             title: String::from_utf8(title.to_vec()).unwrap(),
             title_length: u8::from_le_bytes(*title_length),
             options: options_vec,
-            options_count: u8::from_le_bytes(*options_count),
+            options_count,
             bump: u8::from_le_bytes(*bump),
+            start_ts: i64::from_le_bytes(*start_ts),
+            end_ts: i64::from_le_bytes(*end_ts),
+            allow_revote,
+            status,
+            winner: u8::from_le_bytes(*winner),
+            vote_mode,
+            max_selections: u8::from_le_bytes(*max_selections),
+            vote_mint: Pubkey::new_from_array(*vote_mint),
+            lock_tokens,
+            credits: u64::from_le_bytes(*credits),
+            allowlist_root: *allowlist_root,
+            nft_collection: Pubkey::new_from_array(*nft_collection),
+            reveal_end_ts: i64::from_le_bytes(*reveal_end_ts),
+            unrevealed: u64::from_le_bytes(*unrevealed),
+            quorum: u64::from_le_bytes(*quorum),
+            threshold: u16::from_le_bytes(*threshold),
+            pass_option: u8::from_le_bytes(*pass_option),
+            votes_cast: u64::from_le_bytes(*votes_cast),
+            outcome,
+            timelock: i64::from_le_bytes(*timelock),
+            council: Pubkey::new_from_array(*council),
+            proposal_state,
+            execute_after: i64::from_le_bytes(*execute_after),
+            spend_recipient: Pubkey::new_from_array(*spend_recipient),
+            spend_mint: Pubkey::new_from_array(*spend_mint),
+            spend_amount: u64::from_le_bytes(*spend_amount),
+            veto_threshold: u8::from_le_bytes(*veto_threshold),
+            veto_keys,
+            allow_abstain: allow_abstain[0] != 0,
+            allow_nota: allow_nota[0] != 0,
+            abstain_votes: u64::from_le_bytes(*abstain_votes),
+            nota_votes: u64::from_le_bytes(*nota_votes),
         })
     }
 
-    fn pack_into_slice(&self, dst: &mut [u8]) {
-        let dst = array_mut_ref![dst, 0, Poll::LEN];
+    pub fn pack(src: Self, dst: &mut [u8]) -> Result<(), ProgramError> {
+        if dst.len() < Poll::size(src.options_count) {
+            return Err(ProgramError::AccountDataTooSmall);
+        }
+        let (header, options_dst) = dst.split_at_mut(Poll::HEADER_SIZE);
+        let header = array_mut_ref![header, 0, Poll::HEADER_SIZE];
         let (
             is_initialized_dst,
+            creator_dst,
             id_dst,
             title_dst,
             title_length_dst,
-            options_dst,
-            options_count_dst,
             bump_dst,
+            start_ts_dst,
+            end_ts_dst,
+            allow_revote_dst,
+            status_dst,
+            winner_dst,
+            vote_mode_dst,
+            max_selections_dst,
+            vote_mint_dst,
+            lock_tokens_dst,
+            credits_dst,
+            allowlist_root_dst,
+            nft_collection_dst,
+            reveal_end_ts_dst,
+            unrevealed_dst,
+            quorum_dst,
+            threshold_dst,
+            pass_option_dst,
+            votes_cast_dst,
+            outcome_dst,
+            timelock_dst,
+            council_dst,
+            proposal_state_dst,
+            execute_after_dst,
+            spend_recipient_dst,
+            spend_mint_dst,
+            spend_amount_dst,
+            veto_threshold_dst,
+            veto_keys_dst,
+            allow_abstain_dst,
+            allow_nota_dst,
+            abstain_votes_dst,
+            nota_votes_dst,
+            options_count_dst,
         ) = mut_array_refs![
-            dst,
+            header,
+            1,
+            32,
+            8,
+            POLL_TITLE_SIZE,
+            1,
+            1,
+            8,
+            8,
+            1,
+            1,
+            1,
+            1,
             1,
+            32,
             1,
-            100,
+            8,
+            32,
+            32,
+            8,
+            8,
+            8,
+            2,
             1,
-            PollOption::SIZE * Poll::OPTIONS_COINT,
+            8,
             1,
+            8,
+            32,
+            1,
+            8,
+            32,
+            32,
+            8,
+            1,
+            32 * POLL_VETO_KEYS,
+            1,
+            1,
+            8,
+            8,
             1
         ];
         let Poll {
             is_initialized,
+            creator,
             id,
             title,
             title_length,
             options,
             options_count,
             bump,
-        } = self;
-        is_initialized_dst[0] = *is_initialized as u8;
+            start_ts,
+            end_ts,
+            allow_revote,
+            status,
+            winner,
+            vote_mode,
+            max_selections,
+            vote_mint,
+            lock_tokens,
+            credits,
+            allowlist_root,
+            nft_collection,
+            reveal_end_ts,
+            unrevealed,
+            quorum,
+            threshold,
+            pass_option,
+            votes_cast,
+            outcome,
+            timelock,
+            council,
+            proposal_state,
+            execute_after,
+            spend_recipient,
+            spend_mint,
+            spend_amount,
+            veto_threshold,
+            veto_keys,
+            allow_abstain,
+            allow_nota,
+            abstain_votes,
+            nota_votes,
+        } = src;
+        is_initialized_dst[0] = is_initialized as u8;
+        creator_dst.copy_from_slice(creator.as_ref());
         *id_dst = id.to_le_bytes();
         title_dst.copy_from_slice(title.as_ref());
         *title_length_dst = title_length.to_le_bytes();
+        *bump_dst = bump.to_le_bytes();
+        *start_ts_dst = start_ts.to_le_bytes();
+        *end_ts_dst = end_ts.to_le_bytes();
+        allow_revote_dst[0] = allow_revote as u8;
+        status_dst[0] = status as u8;
+        *winner_dst = winner.to_le_bytes();
+        vote_mode_dst[0] = vote_mode as u8;
+        *max_selections_dst = max_selections.to_le_bytes();
+        vote_mint_dst.copy_from_slice(vote_mint.as_ref());
+        lock_tokens_dst[0] = lock_tokens as u8;
+        *credits_dst = credits.to_le_bytes();
+        *allowlist_root_dst = allowlist_root;
+        nft_collection_dst.copy_from_slice(nft_collection.as_ref());
+        *reveal_end_ts_dst = reveal_end_ts.to_le_bytes();
+        *unrevealed_dst = unrevealed.to_le_bytes();
+        *quorum_dst = quorum.to_le_bytes();
+        *threshold_dst = threshold.to_le_bytes();
+        *pass_option_dst = pass_option.to_le_bytes();
+        *votes_cast_dst = votes_cast.to_le_bytes();
+        outcome_dst[0] = outcome as u8;
+        *timelock_dst = timelock.to_le_bytes();
+        council_dst.copy_from_slice(council.as_ref());
+        proposal_state_dst[0] = proposal_state as u8;
+        *execute_after_dst = execute_after.to_le_bytes();
+        spend_recipient_dst.copy_from_slice(spend_recipient.as_ref());
+        spend_mint_dst.copy_from_slice(spend_mint.as_ref());
+        *spend_amount_dst = spend_amount.to_le_bytes();
+        *veto_threshold_dst = veto_threshold.to_le_bytes();
+        for (key_dst, key) in veto_keys_dst.chunks_exact_mut(32).zip(veto_keys.iter()) {
+            key_dst.copy_from_slice(key.as_ref());
+        }
+        allow_abstain_dst[0] = allow_abstain as u8;
+        allow_nota_dst[0] = allow_nota as u8;
+        *abstain_votes_dst = abstain_votes.to_le_bytes();
+        *nota_votes_dst = nota_votes.to_le_bytes();
+        *options_count_dst = options_count.to_le_bytes();
 
-        for i in 0..*options_count as usize {
-            options.get(i).unwrap().pack_into_slice(
+        for (i, option) in options.iter().take(options_count as usize).enumerate() {
+            option.pack_into_slice(
                 &mut options_dst[(i * PollOption::SIZE)..(i + 1) * PollOption::SIZE],
             )
         }
-
-        //options_dst.copy_from_slice(options.as_ref());
-        *options_count_dst = options_count.to_le_bytes();
-        *bump_dst = bump.to_le_bytes();
+        Ok(())
     }
 }
 
-impl<'a> Poll {
-    fn option_at(data: &'a [u8], idx: u8) -> Option<PollOption> {
-        let op = data
-            .get(PollOption::LEN * idx as usize..PollOption::LEN * (idx + 1) as usize)
-            .and_then(|slice| slice.try_into().ok())
-            .map(|s| PollOption::unpack_unchecked(s))
-            .ok_or(None::<PollOption>)
-            .unwrap();
+impl Poll {
+    fn option_at(data: &[u8], idx: u8) -> Option<PollOption> {
+        data.get(PollOption::LEN * idx as usize..PollOption::LEN * (idx as usize + 1))
+            .and_then(|slice| PollOption::unpack_unchecked(slice).ok())
+    }
 
-        let _ = match op {
-            Ok(opt) => return Some(opt),
-            _ => return None,
+    //read a poll stored with the u8 id layout
+    pub fn unpack_legacy(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, Poll::LEGACY_SIZE];
+        let (is_initialized, id, title, title_length, options, options_count, bump) = array_refs![
+            src,
+            1,
+            1,
+            POLL_TITLE_SIZE,
+            1,
+            PollOption::SIZE * Poll::LEGACY_OPTIONS_COUNT,
+            1,
+            1
+        ];
+        let is_initialized = match is_initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
         };
+
+        let mut options_vec = Vec::new();
+
+        for i in 0..u8::from_le_bytes(*options_count) {
+            options_vec.push(Poll::option_at(options, i).ok_or(ProgramError::InvalidAccountData)?);
+        }
+        //legacy voters voted for a single option with one vote
+        let votes_cast = options_vec.iter().map(|option| option.votes).sum();
+
+        Ok(Poll {
+            is_initialized,
+            creator: Pubkey::default(),
+            id: u8::from_le_bytes(*id) as u64,
+            title: String::from_utf8(title.to_vec()).unwrap(),
+            title_length: u8::from_le_bytes(*title_length),
+            options: options_vec,
+            options_count: u8::from_le_bytes(*options_count),
+            bump: u8::from_le_bytes(*bump),
+            votes_cast,
+            ..Poll::default()
+        })
     }
 
     pub fn add_vote(&mut self, option_id: u8, count: u64) {
@@ -216,11 +1021,202 @@ impl<'a> Poll {
         let poll_option = self.options.get_mut(option_id as usize).unwrap();
         poll_option.add_vote(count);
     }
+
+    //a quadratic allocation has to fit in the voice credits of the poll
+    pub fn within_credits(&self, allocation: &[u16]) -> bool {
+        matches!(PollVoter::credits_cost(allocation), Some(cost) if cost <= self.credits)
+    }
+
+    //abstain and none of the above are choices only when the poll enables them
+    pub fn allows_choice(&self, option_id: u8) -> bool {
+        match option_id {
+            ABSTAIN_OPTION_ID => self.allow_abstain,
+            NOTA_OPTION_ID => self.allow_nota,
+            _ => false,
+        }
+    }
+
+    //add the voter's votes to the options they voted for
+    pub fn add_voter_votes(&mut self, voter: &PollVoter) {
+        for poll_option in self.options.iter_mut() {
+            poll_option.add_vote(voter.option_votes(poll_option.id));
+        }
+        match voter.option_selected {
+            ABSTAIN_OPTION_ID => {
+                self.abstain_votes = self.abstain_votes.saturating_add(voter.weight)
+            }
+            NOTA_OPTION_ID => self.nota_votes = self.nota_votes.saturating_add(voter.weight),
+            _ => {}
+        }
+        if voter.selections != 0
+            || matches!(voter.option_selected, ABSTAIN_OPTION_ID | NOTA_OPTION_ID)
+        {
+            self.votes_cast = self.votes_cast.saturating_add(voter.weight);
+        }
+    }
+
+    pub fn remove_voter_votes(&mut self, voter: &PollVoter) {
+        for poll_option in self.options.iter_mut() {
+            poll_option.remove_vote(voter.option_votes(poll_option.id));
+        }
+        match voter.option_selected {
+            ABSTAIN_OPTION_ID => {
+                self.abstain_votes = self.abstain_votes.saturating_sub(voter.weight)
+            }
+            NOTA_OPTION_ID => self.nota_votes = self.nota_votes.saturating_sub(voter.weight),
+            _ => {}
+        }
+        if voter.selections != 0
+            || matches!(voter.option_selected, ABSTAIN_OPTION_ID | NOTA_OPTION_ID)
+        {
+            self.votes_cast = self.votes_cast.saturating_sub(voter.weight);
+        }
+    }
+
+    //none of the above beats every option
+    pub fn nota_wins(&self) -> bool {
+        self.nota_votes > 0
+            && self
+                .options
+                .iter()
+                .all(|option| option.votes < self.nota_votes)
+    }
+
+    //stop voting and record the option with most votes, 0 if the top is tied,
+    //ranked polls get their winner from the instant runoff tally
+    pub fn finalize(&mut self) {
+        self.status = PollStatus::Finalized;
+        if self.vote_mode == VoteMode::Ranked {
+            return;
+        }
+        let top = self.options.iter().map(|option| option.votes).max();
+        let mut leaders = self
+            .options
+            .iter()
+            .filter(|option| Some(option.votes) == top);
+        self.winner = match (leaders.next(), leaders.next()) {
+            (Some(option), None) if !self.nota_wins() => option.id + 1,
+            _ => 0,
+        };
+        self.decide();
+    }
+
+    //a pending proposal is queued once its poll passed, it can be executed
+    //when the timelock is over
+    pub fn queue_proposal(&mut self, now: i64) {
+        if self.proposal_state == ProposalState::Pending && self.outcome == PollOutcome::Passed {
+            self.proposal_state = ProposalState::Queued;
+            self.execute_after = now.saturating_add(self.timelock);
+        }
+    }
+
+    //outcome of a finalized poll: the quorum has to be met and the pass option
+    //(the winner if none) has to get more than threshold basis points of the votes,
+    //approval ballots select several options so their share is of the ballots cast,
+    //abstentions only count toward the quorum, none of the above counts against
+    //every option and fails the poll when it beats them all
+    pub fn decide(&mut self) {
+        let option_id = self.decided_option();
+        let votes = self
+            .options
+            .get((option_id as usize).wrapping_sub(1))
+            .map_or(0, |option| option.votes);
+        let total = match self.vote_mode {
+            VoteMode::Approval => self.votes_cast.saturating_sub(self.abstain_votes),
+            _ => self
+                .options
+                .iter()
+                .map(|option| option.votes)
+                .sum::<u64>()
+                .saturating_add(self.nota_votes),
+        };
+        let nota_wins = self.nota_wins();
+        self.set_outcome(option_id, votes, total, nota_wins);
+    }
+
+    //ranked polls are decided on the final instant runoff round: the pass
+    //option's votes out of the votes still in play, not the first choices
+    pub fn decide_ranked(&mut self, round_votes: &[u64]) {
+        let option_id = self.decided_option();
+        let votes = round_votes
+            .get((option_id as usize).wrapping_sub(1))
+            .copied()
+            .unwrap_or(0);
+        let total = round_votes
+            .iter()
+            .fold(self.nota_votes, |total, votes| total.saturating_add(*votes));
+        let nota_wins =
+            self.nota_votes > 0 && round_votes.iter().all(|votes| *votes < self.nota_votes);
+        self.set_outcome(option_id, votes, total, nota_wins);
+    }
+
+    //option the threshold applies to
+    fn decided_option(&self) -> u8 {
+        match self.pass_option {
+            0 => self.winner,
+            pass_option => pass_option,
+        }
+    }
+
+    fn set_outcome(&mut self, option_id: u8, votes: u64, total: u64, nota_wins: bool) {
+        self.outcome = if self.votes_cast < self.quorum {
+            PollOutcome::QuorumNotMet
+        } else if option_id != 0
+            && !nota_wins
+            && votes as u128 * 10_000 > self.threshold as u128 * total as u128
+        {
+            PollOutcome::Passed
+        } else {
+            PollOutcome::Failed
+        };
+    }
+
+    pub fn log_tally(&self) {
+        msg!(
+            "poll {} {:?}: winner={}",
+            self.id,
+            self.title.get(..self.title_length as usize).unwrap_or(""),
+            self.winner
+        );
+        msg!(
+            "outcome={:?}, {} vote(s) cast, quorum={}, threshold={}bps",
+            self.outcome,
+            self.votes_cast,
+            self.quorum,
+            self.threshold
+        );
+        for option in self.options.iter() {
+            msg!(
+                "option {} {:?}: {} vote(s)",
+                option.id + 1,
+                option
+                    .title
+                    .get(..option.title_length as usize)
+                    .unwrap_or(""),
+                option.votes
+            );
+        }
+        if self.allow_abstain || self.allow_nota {
+            msg!(
+                "abstain: {} vote(s), none of the above: {} vote(s)",
+                self.abstain_votes,
+                self.nota_votes
+            );
+        }
+        if self.unrevealed > 0 {
+            msg!("{} ballot(s) not revealed", self.unrevealed);
+        }
+    }
 }
 
 impl PollOption {
     pub fn add_vote(&mut self, count: u64) {
-        self.votes += count;
+        //token weights are balances, a tally can not wrap around
+        self.votes = self.votes.saturating_add(count);
+    }
+
+    pub fn remove_vote(&mut self, count: u64) {
+        self.votes = self.votes.saturating_sub(count);
     }
 }
 
@@ -255,12 +1251,104 @@ impl Pack for PollOption {
     }
 }
 
+impl Pack for Delegation {
+    const LEN: usize = Delegation::SIZE;
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, Delegation::LEN];
+        let (is_initialized, delegator, delegate, poll, bump) = array_refs![src, 1, 32, 32, 32, 1];
+        let is_initialized = match is_initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        Ok(Delegation {
+            is_initialized,
+            delegator: Pubkey::new_from_array(*delegator),
+            delegate: Pubkey::new_from_array(*delegate),
+            poll: Pubkey::new_from_array(*poll),
+            bump: u8::from_le_bytes(*bump),
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, Delegation::LEN];
+        let (is_initialized_dst, delegator_dst, delegate_dst, poll_dst, bump_dst) =
+            mut_array_refs![dst, 1, 32, 32, 32, 1];
+        let Delegation {
+            is_initialized,
+            delegator,
+            delegate,
+            poll,
+            bump,
+        } = self;
+
+        is_initialized_dst[0] = *is_initialized as u8;
+        delegator_dst.copy_from_slice(delegator.as_ref());
+        delegate_dst.copy_from_slice(delegate.as_ref());
+        poll_dst.copy_from_slice(poll.as_ref());
+        *bump_dst = bump.to_le_bytes();
+    }
+}
+
+impl Pack for GovernanceConfig {
+    const LEN: usize = GovernanceConfig::SIZE;
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, GovernanceConfig::LEN];
+        let (is_initialized, creator, bump, min_quorum, min_threshold, min_voting_period) =
+            array_refs![src, 1, 32, 1, 8, 2, 8];
+        let is_initialized = match is_initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        Ok(GovernanceConfig {
+            is_initialized,
+            creator: Pubkey::new_from_array(*creator),
+            bump: u8::from_le_bytes(*bump),
+            min_quorum: u64::from_le_bytes(*min_quorum),
+            min_threshold: u16::from_le_bytes(*min_threshold),
+            min_voting_period: i64::from_le_bytes(*min_voting_period),
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, GovernanceConfig::LEN];
+        let (
+            is_initialized_dst,
+            creator_dst,
+            bump_dst,
+            min_quorum_dst,
+            min_threshold_dst,
+            min_voting_period_dst,
+        ) = mut_array_refs![dst, 1, 32, 1, 8, 2, 8];
+        let GovernanceConfig {
+            is_initialized,
+            creator,
+            bump,
+            min_quorum,
+            min_threshold,
+            min_voting_period,
+        } = self;
+
+        is_initialized_dst[0] = *is_initialized as u8;
+        creator_dst.copy_from_slice(creator.as_ref());
+        *bump_dst = bump.to_le_bytes();
+        *min_quorum_dst = min_quorum.to_le_bytes();
+        *min_threshold_dst = min_threshold.to_le_bytes();
+        *min_voting_period_dst = min_voting_period.to_le_bytes();
+    }
+}
+
 impl Pack for PollCount {
     const LEN: usize = PollCount::SIZE;
 
     fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
         let src = array_ref![src, 0, PollCount::LEN];
-        let (is_initialized, count, bump) = array_refs![src, 1, 1, 1];
+        let (is_initialized, count, bump) = array_refs![src, 1, 8, 1];
         //let count = [src[0]];
         let is_initialized = match is_initialized {
             [0] => false,
@@ -270,7 +1358,7 @@ impl Pack for PollCount {
 
         Ok(PollCount {
             is_initialized,
-            count: u8::from_le_bytes(*count),
+            count: u64::from_le_bytes(*count),
             bump: u8::from_le_bytes(*bump),
         })
     }
@@ -278,7 +1366,7 @@ impl Pack for PollCount {
     fn pack_into_slice(&self, dst: &mut [u8]) {
         //let dst = &mut [dst[0]];
         let dst = array_mut_ref![dst, 0, PollCount::LEN];
-        let (is_initialized_dst, count_dst, bump_dst) = mut_array_refs![dst, 1, 1, 1];
+        let (is_initialized_dst, count_dst, bump_dst) = mut_array_refs![dst, 1, 8, 1];
         let PollCount {
             is_initialized,
             count,
@@ -297,11 +1385,10 @@ impl IsInitialized for PollVoter {
     }
 }
 
-impl Pack for PollVoter {
-    const LEN: usize = PollVoter::SIZE;
-
-    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
-        let src = array_ref![src, 0, PollVoter::LEN];
+impl PollVoter {
+    //read a voter stored with the u8 poll id layout
+    pub fn unpack_legacy(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, PollVoter::LEGACY_SIZE];
         let (is_initialized, poll_id, option_selected, bump) = array_refs![src, 1, 1, 1, 1];
         let is_initialized = match is_initialized {
             [0] => false,
@@ -309,29 +1396,326 @@ impl Pack for PollVoter {
             _ => return Err(ProgramError::InvalidInstructionData),
         };
 
+        let option_selected = u8::from_le_bytes(*option_selected);
+
         Ok(PollVoter {
             is_initialized,
-            poll_id: u8::from_le_bytes(*poll_id),
+            poll_id: u8::from_le_bytes(*poll_id) as u64,
+            option_selected,
+            bump: u8::from_le_bytes(*bump),
+            selections: PollVoter::option_mask(option_selected),
+            ballot: 0,
+            weight: 1,
+            commitment: [0; 32],
+            payer: Pubkey::default(),
+            allocation: Vec::new(),
+        })
+    }
+}
+
+impl PollVoter {
+    //the credit allocation of quadratic voters follows the fixed fields
+    pub fn unpack_unchecked(src: &[u8]) -> Result<Self, ProgramError> {
+        let (fixed, allocation) = src
+            .get(..PollVoter::SIZE)
+            .map(|fixed| (fixed, &src[PollVoter::SIZE..]))
+            .ok_or(ProgramError::InvalidAccountData)?;
+        let fixed = array_ref![fixed, 0, PollVoter::SIZE];
+        let (
+            is_initialized,
+            poll_id,
+            option_selected,
+            bump,
+            selections,
+            ballot,
+            weight,
+            commitment,
+            payer,
+        ) = array_refs![fixed, 1, 8, 1, 1, 8, 4, 8, 32, 32];
+        let is_initialized = match is_initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidInstructionData),
+        };
+
+        Ok(PollVoter {
+            is_initialized,
+            poll_id: u64::from_le_bytes(*poll_id),
             option_selected: u8::from_le_bytes(*option_selected),
             bump: u8::from_le_bytes(*bump),
+            selections: u64::from_le_bytes(*selections),
+            ballot: u32::from_le_bytes(*ballot),
+            weight: u64::from_le_bytes(*weight),
+            commitment: *commitment,
+            payer: Pubkey::new_from_array(*payer),
+            allocation: allocation
+                .chunks_exact(2)
+                .map(|votes| u16::from_le_bytes([votes[0], votes[1]]))
+                .collect(),
         })
     }
 
-    fn pack_into_slice(&self, dst: &mut [u8]) {
-        let dst = array_mut_ref![dst, 0, PollVoter::LEN];
-        let (is_initialized_dst, poll_id_dst, option_dst, bump_dst) =
-            mut_array_refs![dst, 1, 1, 1, 1];
+    pub fn pack(src: Self, dst: &mut [u8]) -> Result<(), ProgramError> {
+        if dst.len() < PollVoter::SIZE + 2 * src.allocation.len() {
+            return Err(ProgramError::AccountDataTooSmall);
+        }
+        let (fixed, allocation_dst) = dst.split_at_mut(PollVoter::SIZE);
+        let fixed = array_mut_ref![fixed, 0, PollVoter::SIZE];
+        let (
+            is_initialized_dst,
+            poll_id_dst,
+            option_dst,
+            bump_dst,
+            selections_dst,
+            ballot_dst,
+            weight_dst,
+            commitment_dst,
+            payer_dst,
+        ) = mut_array_refs![fixed, 1, 8, 1, 1, 8, 4, 8, 32, 32];
         let PollVoter {
             is_initialized,
             poll_id,
             option_selected,
             bump,
-        } = self;
+            selections,
+            ballot,
+            weight,
+            commitment,
+            payer,
+            allocation,
+        } = src;
 
-        is_initialized_dst[0] = *is_initialized as u8;
+        is_initialized_dst[0] = is_initialized as u8;
         *poll_id_dst = poll_id.to_le_bytes();
         *option_dst = option_selected.to_le_bytes();
         *bump_dst = bump.to_le_bytes();
+        *selections_dst = selections.to_le_bytes();
+        *ballot_dst = ballot.to_le_bytes();
+        *weight_dst = weight.to_le_bytes();
+        *commitment_dst = commitment;
+        payer_dst.copy_from_slice(payer.as_ref());
+
+        for (votes, votes_dst) in allocation.iter().zip(allocation_dst.chunks_exact_mut(2)) {
+            votes_dst.copy_from_slice(&votes.to_le_bytes());
+        }
+        Ok(())
+    }
+}
+
+impl IsInitialized for BallotBox {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl BallotBox {
+    //everything before the round votes
+    pub const HEADER_SIZE: usize = 1 + 8 + 1 + 1 + 4 + 1 + 1 + 4 + 8;
+    //ballots counted by a single TallyRound
+    pub const TALLY_BATCH_SIZE: u32 = 100;
+
+    pub fn ballots_offset(options_count: u8) -> usize {
+        BallotBox::HEADER_SIZE + 8 * options_count as usize
+    }
+
+    //a ballot is its weight followed by the ranking
+    pub fn ballot_size(options_count: u8) -> usize {
+        8 + options_count as usize
+    }
+
+    pub fn size(options_count: u8, ballots_count: u32) -> usize {
+        BallotBox::ballots_offset(options_count)
+            + BallotBox::ballot_size(options_count) * ballots_count as usize
+    }
+
+    //where a ballot is stored in the account data
+    pub fn ballot_range(&self, ballot: u32) -> Range<usize> {
+        let start = BallotBox::size(self.options_count, ballot);
+        start..start + BallotBox::ballot_size(self.options_count)
+    }
+
+    //write a ballot in place, a ballot without options clears it
+    pub fn write_ballot(ballot: &mut [u8], weight: u64, option_ids: &[u8]) {
+        ballot.fill(0);
+        let (weight_dst, ranking_dst) = ballot.split_at_mut(8);
+        weight_dst.copy_from_slice(&weight.to_le_bytes());
+        ranking_dst[..option_ids.len()].copy_from_slice(option_ids);
+    }
+
+    pub fn unpack_unchecked(src: &[u8]) -> Result<Self, ProgramError> {
+        let header = src
+            .get(..BallotBox::HEADER_SIZE)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        let header = array_ref![header, 0, BallotBox::HEADER_SIZE];
+        let (
+            is_initialized,
+            poll_id,
+            bump,
+            options_count,
+            ballots_count,
+            tally_done,
+            round,
+            cursor,
+            eliminated,
+        ) = array_refs![header, 1, 8, 1, 1, 4, 1, 1, 4, 8];
+        let is_initialized = match is_initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        let tally_done = match tally_done {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        let options_count = u8::from_le_bytes(*options_count);
+        let round_votes = src
+            .get(BallotBox::HEADER_SIZE..BallotBox::ballots_offset(options_count))
+            .ok_or(ProgramError::InvalidAccountData)?
+            .chunks_exact(8)
+            .map(|votes| u64::from_le_bytes(*array_ref![votes, 0, 8]))
+            .collect();
+
+        Ok(BallotBox {
+            is_initialized,
+            poll_id: u64::from_le_bytes(*poll_id),
+            bump: u8::from_le_bytes(*bump),
+            options_count,
+            ballots_count: u32::from_le_bytes(*ballots_count),
+            tally_done,
+            round: u8::from_le_bytes(*round),
+            cursor: u32::from_le_bytes(*cursor),
+            eliminated: u64::from_le_bytes(*eliminated),
+            round_votes,
+        })
+    }
+
+    //only the header and round votes are written, ballots are written in place
+    pub fn pack(src: Self, dst: &mut [u8]) -> Result<(), ProgramError> {
+        if dst.len() < BallotBox::size(src.options_count, src.ballots_count) {
+            return Err(ProgramError::AccountDataTooSmall);
+        }
+        let (header, round_votes_dst) = dst.split_at_mut(BallotBox::HEADER_SIZE);
+        let header = array_mut_ref![header, 0, BallotBox::HEADER_SIZE];
+        let (
+            is_initialized_dst,
+            poll_id_dst,
+            bump_dst,
+            options_count_dst,
+            ballots_count_dst,
+            tally_done_dst,
+            round_dst,
+            cursor_dst,
+            eliminated_dst,
+        ) = mut_array_refs![header, 1, 8, 1, 1, 4, 1, 1, 4, 8];
+        let BallotBox {
+            is_initialized,
+            poll_id,
+            bump,
+            options_count,
+            ballots_count,
+            tally_done,
+            round,
+            cursor,
+            eliminated,
+            round_votes,
+        } = src;
+        is_initialized_dst[0] = is_initialized as u8;
+        *poll_id_dst = poll_id.to_le_bytes();
+        *bump_dst = bump.to_le_bytes();
+        *options_count_dst = options_count.to_le_bytes();
+        *ballots_count_dst = ballots_count.to_le_bytes();
+        tally_done_dst[0] = tally_done as u8;
+        *round_dst = round.to_le_bytes();
+        *cursor_dst = cursor.to_le_bytes();
+        *eliminated_dst = eliminated.to_le_bytes();
+
+        for (votes, votes_dst) in round_votes
+            .iter()
+            .zip(round_votes_dst.chunks_exact_mut(8))
+            .take(options_count as usize)
+        {
+            votes_dst.copy_from_slice(&votes.to_le_bytes());
+        }
+        Ok(())
+    }
+
+    //highest ranked option of a ballot that is still in the running, 0 if exhausted
+    pub fn top_choice(&self, ballot: &[u8]) -> u8 {
+        ballot
+            .iter()
+            .copied()
+            .take_while(|option_id| *option_id != 0)
+            .find(|option_id| self.eliminated & PollVoter::option_mask(*option_id) == 0)
+            .unwrap_or(0)
+    }
+
+    //count the next batch of ballots (account data) for the current round
+    pub fn count_ballots(&mut self, data: &[u8]) {
+        let end = self
+            .ballots_count
+            .min(self.cursor.saturating_add(BallotBox::TALLY_BATCH_SIZE));
+        for ballot in self.cursor..end {
+            let (weight, top_choice) = data
+                .get(self.ballot_range(ballot))
+                .map(|ballot| {
+                    let (weight, ranking) = ballot.split_at(8);
+                    (
+                        u64::from_le_bytes(*array_ref![weight, 0, 8]),
+                        self.top_choice(ranking),
+                    )
+                })
+                .unwrap_or((0, 0));
+            if let Some(votes) = self
+                .round_votes
+                .get_mut(top_choice.wrapping_sub(1) as usize)
+            {
+                *votes = votes.saturating_add(weight);
+            }
+        }
+        self.cursor = end;
+    }
+
+    //close a fully counted round, returns the winner (0 for a tie) once an
+    //option has a majority of the votes still in play, otherwise the options
+    //with the fewest votes are eliminated and the next round starts
+    pub fn finish_round(&mut self) -> Option<u8> {
+        let running: Vec<(u8, u64)> = self
+            .round_votes
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| self.eliminated & (1 << i) == 0)
+            .map(|(i, votes)| (i as u8, *votes))
+            .collect();
+        let active: u64 = running.iter().map(|(_, votes)| votes).sum();
+        let fewest = running.iter().map(|(_, votes)| *votes).min().unwrap_or(0);
+        let last = running
+            .iter()
+            .filter(|(_, votes)| *votes == fewest)
+            .fold(0u64, |mask, (i, _)| mask | 1 << i);
+
+        let winner = if active == 0 {
+            Some(0)
+        } else if let Some((i, _)) = running.iter().find(|(_, votes)| *votes > active / 2) {
+            Some(i + 1)
+        } else if last.count_ones() as usize == running.len() {
+            //everyone left is tied
+            Some(0)
+        } else {
+            None
+        };
+
+        match winner {
+            Some(_) => self.tally_done = true,
+            None => {
+                self.eliminated |= last;
+                self.round += 1;
+                self.cursor = 0;
+                self.round_votes.iter_mut().for_each(|votes| *votes = 0);
+            }
+        }
+        winner
     }
 }
 
@@ -427,3 +1811,338 @@ impl Pack for Voter {
         *vote_count_dst = vote_count.to_le_bytes();
     }
 }
+
+impl NftMetadata {
+    //account key of a metadata account
+    pub const METADATA_V1: u8 = 4;
+
+    //metadata accounts are zero padded, accounts written before collections
+    //existed read as having none
+    pub fn unpack(src: &[u8]) -> Result<Self, ProgramError> {
+        NftMetadata::deserialize(&mut &src[..])
+            .ok()
+            .filter(|metadata| metadata.key == NftMetadata::METADATA_V1)
+            .ok_or(ProgramError::InvalidAccountData)
+    }
+
+    //collection the nft is a verified member of
+    pub fn verified_collection(&self) -> Option<Pubkey> {
+        self.collection
+            .as_ref()
+            .filter(|collection| collection.verified)
+            .map(|collection| collection.key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //a poll with options tallied as given
+    fn tallied_poll(vote_mode: VoteMode, option_votes: &[u64]) -> Poll {
+        let options: Vec<PollOption> = option_votes
+            .iter()
+            .enumerate()
+            .map(|(i, votes)| PollOption {
+                id: i as u8,
+                title: String::new(),
+                title_length: 0,
+                votes: *votes,
+            })
+            .collect();
+        Poll {
+            is_initialized: true,
+            options_count: options.len() as u8,
+            options,
+            vote_mode,
+            votes_cast: option_votes.iter().sum(),
+            ..Poll::default()
+        }
+    }
+
+    //a ballot box holding the ballots, weight and ranking each
+    fn filled_ballot_box(options_count: u8, ballots: &[(u64, &[u8])]) -> (BallotBox, Vec<u8>) {
+        let ballot_box = BallotBox {
+            is_initialized: true,
+            poll_id: 0,
+            bump: 0,
+            options_count,
+            ballots_count: ballots.len() as u32,
+            tally_done: false,
+            round: 0,
+            cursor: 0,
+            eliminated: 0,
+            round_votes: vec![0; options_count as usize],
+        };
+        let mut data = vec![0; BallotBox::size(options_count, ballots.len() as u32)];
+        for (i, (weight, ranking)) in ballots.iter().enumerate() {
+            let range = ballot_box.ballot_range(i as u32);
+            BallotBox::write_ballot(&mut data[range], *weight, ranking);
+        }
+        (ballot_box, data)
+    }
+
+    //run the instant runoff to the end like repeated TallyRounds
+    fn run_tally(ballot_box: &mut BallotBox, data: &[u8]) -> u8 {
+        loop {
+            ballot_box.count_ballots(data);
+            if ballot_box.cursor < ballot_box.ballots_count {
+                continue;
+            }
+            if let Some(winner) = ballot_box.finish_round() {
+                return winner;
+            }
+        }
+    }
+
+    //a quadratic voter with the given allocation and weight
+    fn quadratic_voter(allocation: &[u16], weight: u64) -> PollVoter {
+        PollVoter {
+            is_initialized: true,
+            poll_id: 0,
+            option_selected: 0,
+            bump: 0,
+            selections: allocation
+                .iter()
+                .enumerate()
+                .filter(|(_, votes)| **votes > 0)
+                .fold(0, |mask, (i, _)| mask | PollVoter::option_mask(i as u8 + 1)),
+            ballot: 0,
+            weight,
+            commitment: [0; 32],
+            payer: Pubkey::default(),
+            allocation: allocation.to_vec(),
+        }
+    }
+
+    #[test]
+    fn quadratic_allocations_are_checked_against_the_budget() {
+        let mut poll = tallied_poll(VoteMode::Quadratic, &[0, 0]);
+        poll.credits = 25;
+
+        assert_eq!(PollVoter::credits_cost(&[3, 4]), Some(25));
+        assert!(poll.within_credits(&[3, 4]));
+        assert!(poll.within_credits(&[5]));
+        assert!(!poll.within_credits(&[3, 5]));
+        assert!(!poll.within_credits(&[6]));
+
+        poll.credits = u64::MAX;
+        assert_eq!(
+            PollVoter::credits_cost(&[u16::MAX; 2]),
+            Some(2 * (u16::MAX as u64).pow(2))
+        );
+        assert!(poll.within_credits(&[u16::MAX; 64]));
+    }
+
+    #[test]
+    fn quadratic_votes_saturate_with_large_weights() {
+        let voter = quadratic_voter(&[2, 0], u64::MAX);
+        assert_eq!(voter.option_votes(0), u64::MAX);
+        assert_eq!(voter.option_votes(1), 0);
+
+        let mut poll = tallied_poll(VoteMode::Quadratic, &[0, 0]);
+        poll.add_voter_votes(&voter);
+        poll.add_voter_votes(&voter);
+        assert_eq!(poll.options[0].votes, u64::MAX);
+        assert_eq!(poll.votes_cast, u64::MAX);
+    }
+
+    #[test]
+    fn quadratic_revote_replaces_the_allocation() {
+        let mut poll = tallied_poll(VoteMode::Quadratic, &[0, 0, 0]);
+        let first = quadratic_voter(&[3, 1, 0], 2);
+        poll.add_voter_votes(&first);
+        assert_eq!(poll.votes_cast, 2);
+
+        //a revote takes the old allocation out before adding the new one
+        let second = quadratic_voter(&[0, 0, 4], 2);
+        poll.remove_voter_votes(&first);
+        poll.add_voter_votes(&second);
+        let votes: Vec<u64> = poll.options.iter().map(|option| option.votes).collect();
+        assert_eq!(votes, vec![0, 0, 8]);
+        assert_eq!(poll.votes_cast, 2);
+    }
+
+    //a voter whose ballot selects option_ids (or a built-in choice)
+    fn ballot_voter(option_ids: &[u8], weight: u64) -> PollVoter {
+        PollVoter {
+            is_initialized: true,
+            poll_id: 0,
+            option_selected: option_ids.first().copied().unwrap_or(0),
+            bump: 0,
+            selections: option_ids.iter().fold(0, |mask, option_id| {
+                mask | PollVoter::option_mask(*option_id)
+            }),
+            ballot: 0,
+            weight,
+            commitment: [0; 32],
+            payer: Pubkey::default(),
+            allocation: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn abstentions_only_count_toward_the_quorum() {
+        let mut poll = tallied_poll(VoteMode::Single, &[0, 0]);
+        poll.allow_abstain = true;
+        poll.quorum = 15;
+        poll.threshold = 5_000;
+        poll.add_voter_votes(&ballot_voter(&[1], 6));
+        poll.add_voter_votes(&ballot_voter(&[2], 4));
+
+        poll.finalize();
+        assert_eq!(poll.outcome, PollOutcome::QuorumNotMet);
+
+        //abstentions meet the quorum without diluting option 1's 60%
+        poll.add_voter_votes(&ballot_voter(&[ABSTAIN_OPTION_ID], 10));
+        assert_eq!(poll.votes_cast, 20);
+        assert_eq!(poll.abstain_votes, 10);
+        poll.finalize();
+        assert_eq!(poll.winner, 1);
+        assert_eq!(poll.outcome, PollOutcome::Passed);
+    }
+
+    #[test]
+    fn none_of_the_above_beating_every_option_fails_the_poll() {
+        let mut poll = tallied_poll(VoteMode::Single, &[3, 4]);
+        poll.allow_nota = true;
+        poll.add_voter_votes(&ballot_voter(&[NOTA_OPTION_ID], 5));
+
+        poll.finalize();
+        assert!(poll.nota_wins());
+        assert_eq!(poll.winner, 0);
+        assert_eq!(poll.outcome, PollOutcome::Failed);
+
+        //a pass option does not help against none of the above
+        poll.pass_option = 2;
+        poll.finalize();
+        assert_eq!(poll.outcome, PollOutcome::Failed);
+    }
+
+    #[test]
+    fn none_of_the_above_counts_against_every_share() {
+        let mut poll = tallied_poll(VoteMode::Single, &[3, 4]);
+        poll.allow_nota = true;
+        poll.add_voter_votes(&ballot_voter(&[NOTA_OPTION_ID], 4));
+
+        //option 2 has 4 of 11 votes
+        poll.threshold = 3_000;
+        poll.finalize();
+        assert!(!poll.nota_wins());
+        assert_eq!(poll.winner, 2);
+        assert_eq!(poll.outcome, PollOutcome::Passed);
+
+        poll.threshold = 5_000;
+        poll.finalize();
+        assert_eq!(poll.outcome, PollOutcome::Failed);
+    }
+
+    #[test]
+    fn approval_shares_are_of_the_ballots_cast() {
+        let mut poll = tallied_poll(VoteMode::Approval, &[0, 0]);
+        poll.allow_abstain = true;
+        poll.threshold = 6_666;
+        poll.add_voter_votes(&ballot_voter(&[1, 2], 1));
+        poll.add_voter_votes(&ballot_voter(&[1], 1));
+        poll.add_voter_votes(&ballot_voter(&[ABSTAIN_OPTION_ID], 1));
+        assert_eq!(poll.votes_cast, 3);
+
+        //option 1 is on both ballots that were not abstentions, not 2 of 3 votes
+        poll.finalize();
+        assert_eq!(poll.winner, 1);
+        assert_eq!(poll.outcome, PollOutcome::Passed);
+
+        //option 2 is on half of them
+        poll.pass_option = 2;
+        poll.threshold = 5_000;
+        poll.finalize();
+        assert_eq!(poll.outcome, PollOutcome::Failed);
+    }
+
+    #[test]
+    fn text_is_padded_by_bytes() {
+        let title = pad_text("Déjà vu", POLL_TITLE_SIZE);
+        assert_eq!(title.len(), POLL_TITLE_SIZE);
+        assert!(title.starts_with("Déjà vu "));
+        assert_eq!(
+            pad_text(&"é".repeat(25), POLL_OPTION_SIZE).len(),
+            POLL_OPTION_SIZE
+        );
+    }
+
+    #[test]
+    fn ballots_are_counted_in_batches() {
+        let ballots: Vec<(u64, &[u8])> = (0..150).map(|_| (1, &[1u8][..])).collect();
+        let (mut ballot_box, data) = filled_ballot_box(2, &ballots);
+
+        ballot_box.count_ballots(&data);
+        assert_eq!(ballot_box.cursor, BallotBox::TALLY_BATCH_SIZE);
+        assert_eq!(ballot_box.round_votes, vec![100, 0]);
+
+        ballot_box.count_ballots(&data);
+        assert_eq!(ballot_box.cursor, 150);
+        assert_eq!(ballot_box.round_votes, vec![150, 0]);
+        assert_eq!(ballot_box.finish_round(), Some(1));
+        assert!(ballot_box.tally_done);
+    }
+
+    #[test]
+    fn options_tied_last_are_eliminated_together() {
+        let (mut ballot_box, data) =
+            filled_ballot_box(4, &[(3, &[1]), (3, &[2]), (1, &[3, 1]), (1, &[4, 2])]);
+
+        ballot_box.count_ballots(&data);
+        assert_eq!(ballot_box.finish_round(), None);
+        assert_eq!(ballot_box.eliminated, 0b1100);
+        assert_eq!(ballot_box.round, 1);
+        assert_eq!(ballot_box.cursor, 0);
+        assert_eq!(ballot_box.round_votes, vec![0; 4]);
+        assert!(!ballot_box.tally_done);
+
+        //both eliminated ballots transfer, leaving a tie
+        ballot_box.count_ballots(&data);
+        assert_eq!(ballot_box.round_votes, vec![4, 4, 0, 0]);
+        assert_eq!(ballot_box.finish_round(), Some(0));
+    }
+
+    #[test]
+    fn majority_is_of_the_votes_still_in_play() {
+        //the ballots of option 3 are exhausted once it is eliminated
+        let (mut ballot_box, data) = filled_ballot_box(3, &[(4, &[1]), (3, &[2]), (2, &[3])]);
+
+        assert_eq!(run_tally(&mut ballot_box, &data), 1);
+        assert_eq!(ballot_box.round, 1);
+        assert_eq!(ballot_box.round_votes, vec![4, 3, 0]);
+    }
+
+    #[test]
+    fn all_options_tied_is_no_winner() {
+        let (mut ballot_box, data) = filled_ballot_box(3, &[(2, &[1]), (2, &[2]), (2, &[3])]);
+
+        ballot_box.count_ballots(&data);
+        assert_eq!(ballot_box.finish_round(), Some(0));
+        assert!(ballot_box.tally_done);
+
+        let (mut ballot_box, data) = filled_ballot_box(3, &[]);
+        ballot_box.count_ballots(&data);
+        assert_eq!(ballot_box.finish_round(), Some(0));
+    }
+
+    #[test]
+    fn ranked_poll_is_decided_on_the_final_round() {
+        //option 1 leads the first choices, option 2 wins once option 3 is eliminated
+        let (mut ballot_box, data) =
+            filled_ballot_box(3, &[(4, &[1, 3]), (3, &[2, 3]), (2, &[3, 2])]);
+        let mut poll = tallied_poll(VoteMode::Ranked, &[4, 3, 2]);
+        poll.threshold = 5_000;
+
+        poll.winner = run_tally(&mut ballot_box, &data);
+        assert_eq!(poll.winner, 2);
+        assert_eq!(ballot_box.round_votes, vec![4, 5, 0]);
+
+        poll.decide();
+        assert_eq!(poll.outcome, PollOutcome::Failed);
+        poll.decide_ranked(&ballot_box.round_votes);
+        assert_eq!(poll.outcome, PollOutcome::Passed);
+    }
+}
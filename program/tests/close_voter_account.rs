@@ -0,0 +1,75 @@
+mod common;
+
+use common::{poll_instruction, poll_pda, process, voter_pda, TestAccount};
+use program::error::PollError;
+use program::state::PollVoter;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+
+//close the voter pda of a deleted poll, the delegate paid for the pda,
+//returns the result and the lamports of the voter, rent payer and voter pda
+fn close_voter_account(rent_payer: Option<Pubkey>) -> (Result<(), ProgramError>, [u64; 3]) {
+    let program_id = Pubkey::new_unique();
+    let creator = Pubkey::new_unique();
+    let voter = Pubkey::new_unique();
+    let delegate = Pubkey::new_unique();
+    let (poll_key, _) = poll_pda(&program_id, &creator, 1);
+    let (voter_pda_key, voter_bump) = voter_pda(&program_id, &creator, 1, &voter);
+
+    let mut voter_pda_data = vec![0; PollVoter::SIZE];
+    PollVoter::pack(
+        PollVoter {
+            is_initialized: true,
+            poll_id: 1,
+            option_selected: 1,
+            bump: voter_bump,
+            selections: 1,
+            ballot: 0,
+            weight: 1,
+            commitment: [0; 32],
+            payer: delegate,
+            allocation: Vec::new(),
+        },
+        &mut voter_pda_data,
+    )
+    .unwrap();
+
+    let mut accounts = [
+        //the creator already deleted the poll
+        TestAccount::new(poll_key, 0).read_only(),
+        TestAccount::program_owned(voter_pda_key, 1_000, voter_pda_data, program_id),
+        TestAccount::new(voter, 0).signer(),
+        TestAccount::new(rent_payer.unwrap_or(delegate), 0),
+        TestAccount::new(creator, 0).read_only(),
+    ];
+
+    let result = process(&program_id, &mut accounts, &poll_instruction(4, 1));
+    let lamports = [
+        accounts[2].lamports,
+        accounts[3].lamports,
+        accounts[1].lamports,
+    ];
+    (result, lamports)
+}
+
+#[test]
+fn voter_pda_rent_goes_back_to_the_delegate_that_paid_it() {
+    let (result, [voter, rent_payer, voter_pda]) = close_voter_account(None);
+
+    assert_eq!(result, Ok(()));
+    assert_eq!(voter, 0);
+    assert_eq!(rent_payer, 1_000);
+    assert_eq!(voter_pda, 0);
+}
+
+#[test]
+fn voter_pda_rent_can_not_go_to_another_account() {
+    let (result, [voter, rent_payer, voter_pda]) = close_voter_account(Some(Pubkey::new_unique()));
+
+    assert_eq!(
+        result,
+        Err(ProgramError::from(PollError::RentPayerMismatch))
+    );
+    assert_eq!(voter + rent_payer, 0);
+    assert_eq!(voter_pda, 1_000);
+}
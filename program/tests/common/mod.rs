@@ -0,0 +1,172 @@
+//fixtures shared by the integration tests, not every test uses all of them
+#![allow(dead_code)]
+
+use program::processor::{governance_config_seeds, poll_seeds, seed_refs, Processor};
+use program::state::{GovernanceConfig, Poll, PollOption, POLL_OPTION_SIZE, POLL_TITLE_SIZE};
+use solana_program::account_info::AccountInfo;
+use solana_program::program_error::ProgramError;
+use solana_program::program_pack::Pack;
+use solana_program::pubkey::Pubkey;
+use solana_program::system_program;
+
+//an open "Lunch" poll with the options "Yes" and "No"
+pub fn poll(creator: Pubkey, id: u64, bump: u8) -> Poll {
+    let options: Vec<PollOption> = ["Yes", "No"]
+        .iter()
+        .enumerate()
+        .map(|(i, title)| {
+            PollOption::new(
+                i as u8,
+                format!("{:<width$}", title, width = POLL_OPTION_SIZE),
+                title.len() as u8,
+            )
+        })
+        .collect();
+    Poll {
+        is_initialized: true,
+        creator,
+        id,
+        title: format!("{:<width$}", "Lunch", width = POLL_TITLE_SIZE),
+        title_length: 5,
+        options_count: options.len() as u8,
+        options,
+        bump,
+        ..Poll::default()
+    }
+}
+
+pub fn poll_data(poll: Poll) -> Vec<u8> {
+    let mut data = vec![0; Poll::size(poll.options_count)];
+    Poll::pack(poll, &mut data).unwrap();
+    data
+}
+
+//poll pda of a creator's poll and its bump
+pub fn poll_pda(program_id: &Pubkey, creator: &Pubkey, id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&seed_refs(&poll_seeds(creator, id)), program_id)
+}
+
+//pda of a voter in a poll and its bump
+pub fn voter_pda(program_id: &Pubkey, creator: &Pubkey, id: u64, voter: &Pubkey) -> (Pubkey, u8) {
+    let mut seeds = poll_seeds(creator, id);
+    let (_, bump) = Pubkey::find_program_address(&seed_refs(&seeds), program_id);
+    seeds.push(vec![bump]);
+    seeds.push(voter.to_bytes().to_vec());
+    Pubkey::find_program_address(&seed_refs(&seeds), program_id)
+}
+
+//governance config pda of a creator and its bump
+pub fn governance_config_pda(program_id: &Pubkey, creator: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&seed_refs(&governance_config_seeds(creator)), program_id)
+}
+
+//a creator's governance config, already set
+pub fn governance_config(
+    program_id: &Pubkey,
+    creator: &Pubkey,
+    min_quorum: u64,
+    min_threshold: u16,
+    min_voting_period: i64,
+) -> TestAccount {
+    let (key, bump) = governance_config_pda(program_id, creator);
+    let mut data = vec![0; GovernanceConfig::SIZE];
+    GovernanceConfig::pack(
+        GovernanceConfig {
+            is_initialized: true,
+            creator: *creator,
+            bump,
+            min_quorum,
+            min_threshold,
+            min_voting_period,
+        },
+        &mut data,
+    )
+    .unwrap();
+    TestAccount::program_owned(key, 1_000, data, *program_id).read_only()
+}
+
+//an account passed to an instruction, owns what its AccountInfo borrows
+pub struct TestAccount {
+    pub key: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub owner: Pubkey,
+    pub executable: bool,
+}
+
+impl TestAccount {
+    //an empty wallet or pda, owned by the system program
+    pub fn new(key: Pubkey, lamports: u64) -> Self {
+        TestAccount {
+            key,
+            is_signer: false,
+            is_writable: true,
+            lamports,
+            data: Vec::new(),
+            owner: system_program::id(),
+            executable: false,
+        }
+    }
+
+    //an account of the program holding data
+    pub fn program_owned(key: Pubkey, lamports: u64, data: Vec<u8>, program_id: Pubkey) -> Self {
+        TestAccount {
+            data,
+            owner: program_id,
+            ..TestAccount::new(key, lamports)
+        }
+    }
+
+    pub fn signer(self) -> Self {
+        TestAccount {
+            is_signer: true,
+            ..self
+        }
+    }
+
+    pub fn read_only(self) -> Self {
+        TestAccount {
+            is_writable: false,
+            ..self
+        }
+    }
+
+    pub fn system_program() -> Self {
+        TestAccount {
+            executable: true,
+            ..TestAccount::new(system_program::id(), 0).read_only()
+        }
+    }
+
+    pub fn info(&mut self) -> AccountInfo<'_> {
+        AccountInfo::new(
+            &self.key,
+            self.is_signer,
+            self.is_writable,
+            &mut self.lamports,
+            &mut self.data,
+            &self.owner,
+            self.executable,
+            0,
+        )
+    }
+}
+
+//run an instruction, the accounts keep its changes
+pub fn process(
+    program_id: &Pubkey,
+    accounts: &mut [TestAccount],
+    instruction_data: &[u8],
+) -> Result<(), ProgramError> {
+    let infos: Vec<AccountInfo> = accounts.iter_mut().map(TestAccount::info).collect();
+    Processor::process_instruction(program_id, &infos, instruction_data)
+}
+
+//instruction data of the instructions that only take a poll id
+pub fn poll_instruction(tag: u8, poll_id: u64) -> Vec<u8> {
+    let mut instruction_data = vec![tag];
+    instruction_data.extend_from_slice(&poll_id.to_le_bytes());
+    instruction_data
+}
@@ -0,0 +1,88 @@
+mod common;
+
+use common::{governance_config_pda, process, TestAccount};
+use program::error::PollError;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+
+//CreatePoll instruction data with only the title and options
+fn create_poll_instruction(title: &str, options: &[&str]) -> Vec<u8> {
+    let mut instruction_data = vec![0, title.len() as u8];
+    instruction_data.extend_from_slice(title.as_bytes());
+    instruction_data.push(options.len() as u8);
+    instruction_data.extend(options.iter().map(|option| option.len() as u8));
+    for option in options {
+        instruction_data.extend_from_slice(option.as_bytes());
+    }
+    instruction_data
+}
+
+//a "Lunch" poll paying amount lamports out of the treasury, voting ends at 1000000
+fn spend_poll_instruction(recipient: &Pubkey, amount: u64) -> Vec<u8> {
+    let mut instruction_data = create_poll_instruction("Lunch", &["Yes", "No"]);
+    instruction_data.extend_from_slice(&0i64.to_le_bytes());
+    instruction_data.extend_from_slice(&1_000_000i64.to_le_bytes());
+    //default rules and options, up to the council
+    instruction_data.extend_from_slice(&[0; 167]);
+    instruction_data.extend_from_slice(recipient.as_ref());
+    instruction_data.extend_from_slice(Pubkey::default().as_ref());
+    instruction_data.extend_from_slice(&amount.to_le_bytes());
+    //no veto authority
+    instruction_data.extend_from_slice(&[0, 0]);
+    instruction_data
+}
+
+fn create_poll(title: &str, options: &[&str]) -> Result<(), ProgramError> {
+    let program_id = Pubkey::new_unique();
+    let mut accounts = [
+        TestAccount::new(Pubkey::new_unique(), 0),
+        TestAccount::new(Pubkey::new_unique(), 0),
+        TestAccount::system_program(),
+        TestAccount::new(Pubkey::new_unique(), 1_000_000).signer(),
+    ];
+    process(
+        &program_id,
+        &mut accounts,
+        &create_poll_instruction(title, options),
+    )
+}
+
+#[test]
+fn title_over_100_bytes_is_rejected() {
+    let invalid = Err(ProgramError::from(PollError::InvalidInstrucitonData));
+
+    assert_eq!(create_poll(&"a".repeat(101), &["Yes", "No"]), invalid);
+    //51 chars but 102 bytes
+    assert_eq!(create_poll(&"é".repeat(51), &["Yes", "No"]), invalid);
+}
+
+#[test]
+fn option_over_50_bytes_is_rejected() {
+    let invalid = Err(ProgramError::from(PollError::InvalidInstrucitonData));
+
+    assert_eq!(create_poll("Lunch", &["Yes", &"b".repeat(51)]), invalid);
+    assert_eq!(create_poll("Lunch", &[&"ü".repeat(26), "No"]), invalid);
+}
+
+#[test]
+fn spend_poll_needs_a_governance_config() {
+    let program_id = Pubkey::new_unique();
+    let creator = Pubkey::new_unique();
+    let mut accounts = [
+        TestAccount::new(Pubkey::new_unique(), 0),
+        TestAccount::new(Pubkey::new_unique(), 0),
+        TestAccount::system_program(),
+        TestAccount::new(creator, 1_000_000).signer(),
+        TestAccount::new(governance_config_pda(&program_id, &creator).0, 0).read_only(),
+    ];
+
+    let result = process(
+        &program_id,
+        &mut accounts,
+        &spend_poll_instruction(&Pubkey::new_unique(), 1_000),
+    );
+    assert_eq!(
+        result,
+        Err(ProgramError::from(PollError::GovernanceConfigNotSet))
+    );
+}
@@ -0,0 +1,153 @@
+mod common;
+
+use borsh::BorshSerialize;
+use common::{
+    governance_config, governance_config_pda, poll, poll_data, poll_instruction, poll_pda, process,
+    TestAccount,
+};
+use program::error::PollError;
+use program::state::ProposalInstruction;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+
+//instruction data of SetGovernanceConfig
+fn set_governance_config_instruction(
+    min_quorum: u64,
+    min_threshold: u16,
+    min_voting_period: i64,
+) -> Vec<u8> {
+    let mut instruction_data = vec![20];
+    instruction_data.extend_from_slice(&min_quorum.to_le_bytes());
+    instruction_data.extend_from_slice(&min_threshold.to_le_bytes());
+    instruction_data.extend_from_slice(&min_voting_period.to_le_bytes());
+    instruction_data
+}
+
+//attach an instruction to a poll with the given rules, voting from 1000 to
+//1000 + voting_period, returns the result
+fn add_proposal_instruction(
+    quorum: u64,
+    threshold: u16,
+    voting_period: i64,
+    config: Option<(u64, u16, i64)>,
+) -> Result<(), ProgramError> {
+    let program_id = Pubkey::new_unique();
+    let creator = Pubkey::new_unique();
+    let (poll_key, bump) = poll_pda(&program_id, &creator, 1);
+
+    let mut executable_poll = poll(creator, 1, bump);
+    executable_poll.quorum = quorum;
+    executable_poll.threshold = threshold;
+    executable_poll.start_ts = 1_000;
+    executable_poll.end_ts = 1_000 + voting_period;
+
+    let config_account = match config {
+        Some((min_quorum, min_threshold, min_voting_period)) => governance_config(
+            &program_id,
+            &creator,
+            min_quorum,
+            min_threshold,
+            min_voting_period,
+        ),
+        None => TestAccount::new(governance_config_pda(&program_id, &creator).0, 0).read_only(),
+    };
+
+    let mut accounts = [
+        TestAccount::program_owned(poll_key, 1_000_000, poll_data(executable_poll), program_id),
+        TestAccount::new(Pubkey::new_unique(), 0),
+        TestAccount::new(creator, 1_000_000).signer(),
+        TestAccount::system_program(),
+        config_account,
+    ];
+
+    let mut instruction_data = poll_instruction(14, 1);
+    instruction_data.extend(
+        ProposalInstruction {
+            program_id: Pubkey::new_unique(),
+            accounts: Vec::new(),
+            data: Vec::new(),
+        }
+        .try_to_vec()
+        .unwrap(),
+    );
+    process(&program_id, &mut accounts, &instruction_data)
+}
+
+#[test]
+fn governance_config_can_only_be_set_once() {
+    let program_id = Pubkey::new_unique();
+    let creator = Pubkey::new_unique();
+
+    let mut accounts = [
+        governance_config(&program_id, &creator, 10, 5_000, 86_400),
+        TestAccount::new(creator, 1_000_000).signer(),
+        TestAccount::system_program(),
+    ];
+    accounts[0].is_writable = true;
+
+    let result = process(
+        &program_id,
+        &mut accounts,
+        &set_governance_config_instruction(0, 0, 1),
+    );
+    assert_eq!(
+        result,
+        Err(ProgramError::from(PollError::GovernanceConfigSet))
+    );
+}
+
+#[test]
+fn governance_config_needs_a_quorum_and_voting_period() {
+    let program_id = Pubkey::new_unique();
+    let creator = Pubkey::new_unique();
+
+    for (instruction_data, error) in [
+        (
+            set_governance_config_instruction(0, 5_000, 86_400),
+            PollError::InvalidInstrucitonData,
+        ),
+        (
+            set_governance_config_instruction(10, 10_001, 86_400),
+            PollError::InvalidInstrucitonData,
+        ),
+        (
+            set_governance_config_instruction(10, 5_000, 0),
+            PollError::InvalidVotingWindow,
+        ),
+    ] {
+        let mut accounts = [
+            TestAccount::new(governance_config_pda(&program_id, &creator).0, 0),
+            TestAccount::new(creator, 1_000_000).signer(),
+            TestAccount::system_program(),
+        ];
+        let result = process(&program_id, &mut accounts, &instruction_data);
+        assert_eq!(result, Err(ProgramError::from(error)));
+    }
+}
+
+#[test]
+fn executable_poll_needs_a_governance_config() {
+    assert_eq!(
+        add_proposal_instruction(10, 5_000, 86_400, None),
+        Err(ProgramError::from(PollError::GovernanceConfigNotSet))
+    );
+}
+
+#[test]
+fn executable_poll_below_the_governance_config_is_rejected() {
+    let config = Some((10, 5_000, 86_400));
+    let rules_not_met = Err(ProgramError::from(PollError::GovernanceRulesNotMet));
+
+    assert_eq!(
+        add_proposal_instruction(0, 5_000, 86_400, config),
+        rules_not_met
+    );
+    assert_eq!(
+        add_proposal_instruction(10, 0, 86_400, config),
+        rules_not_met
+    );
+    assert_eq!(
+        add_proposal_instruction(10, 5_000, 5, config),
+        rules_not_met
+    );
+}
@@ -0,0 +1,74 @@
+mod common;
+
+use common::{poll, poll_data, poll_instruction, poll_pda, process, TestAccount};
+use program::error::PollError;
+use program::state::{Poll, PollOutcome, POLL_VETO_KEYS};
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+
+//run VetoPoll on a passed poll with the given signers, returns the result
+//and the poll after it
+fn veto(
+    veto_keys: &[Pubkey],
+    veto_threshold: u8,
+    signers: &[Pubkey],
+) -> (Result<(), ProgramError>, Poll) {
+    let program_id = Pubkey::new_unique();
+    let creator = Pubkey::new_unique();
+    let (poll_key, bump) = poll_pda(&program_id, &creator, 1);
+
+    let mut passed_poll = poll(creator, 1, bump);
+    passed_poll.outcome = PollOutcome::Passed;
+    passed_poll.veto_threshold = veto_threshold;
+    passed_poll.veto_keys = [Pubkey::default(); POLL_VETO_KEYS];
+    passed_poll.veto_keys[..veto_keys.len()].copy_from_slice(veto_keys);
+
+    let mut accounts = vec![TestAccount::program_owned(
+        poll_key,
+        1_000_000,
+        poll_data(passed_poll),
+        program_id,
+    )];
+    accounts.extend(
+        signers
+            .iter()
+            .map(|signer| TestAccount::new(*signer, 0).signer().read_only()),
+    );
+
+    let result = process(&program_id, &mut accounts, &poll_instruction(19, 1));
+    let poll = Poll::unpack_unchecked(&accounts[0].data).unwrap();
+    (result, poll)
+}
+
+#[test]
+fn veto_keys_reaching_the_threshold_veto_the_poll() {
+    let veto_keys: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+
+    let (result, poll) = veto(&veto_keys, 2, &[veto_keys[0], veto_keys[2]]);
+
+    assert_eq!(result, Ok(()));
+    assert_eq!(poll.outcome, PollOutcome::Vetoed);
+}
+
+#[test]
+fn duplicate_veto_signer_is_counted_once() {
+    let veto_keys: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+
+    let (result, poll) = veto(&veto_keys, 2, &[veto_keys[1], veto_keys[1]]);
+
+    assert_eq!(result, Err(ProgramError::from(PollError::Unauthorized)));
+    assert_eq!(poll.outcome, PollOutcome::Passed);
+}
+
+#[test]
+fn signer_outside_the_veto_keys_is_not_counted() {
+    let veto_keys: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+
+    let (result, poll) = veto(&veto_keys, 1, &[Pubkey::new_unique()]);
+    assert_eq!(result, Err(ProgramError::from(PollError::Unauthorized)));
+    assert_eq!(poll.outcome, PollOutcome::Passed);
+
+    let (result, poll) = veto(&veto_keys, 2, &[veto_keys[0], Pubkey::new_unique()]);
+    assert_eq!(result, Err(ProgramError::from(PollError::Unauthorized)));
+    assert_eq!(poll.outcome, PollOutcome::Passed);
+}
@@ -0,0 +1,41 @@
+mod common;
+
+use common::{poll, poll_data, poll_instruction, poll_pda, process, voter_pda, TestAccount};
+use program::error::PollError;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+use solana_program::system_program;
+
+#[test]
+fn vote_on_behalf_of_another_voter_is_rejected() {
+    let program_id = Pubkey::new_unique();
+    let creator = Pubkey::new_unique();
+    let voter = Pubkey::new_unique();
+    let (poll_key, bump) = poll_pda(&program_id, &creator, 1);
+    let (voter_pda_key, _) = voter_pda(&program_id, &creator, 1, &voter);
+
+    let mut accounts = [
+        TestAccount::program_owned(
+            poll_key,
+            1_000_000,
+            poll_data(poll(creator, 1, bump)),
+            program_id,
+        ),
+        TestAccount::new(voter_pda_key, 0),
+        //someone else submits the vote, the voter did not sign
+        TestAccount::new(voter, 1_000_000),
+        TestAccount::system_program(),
+    ];
+
+    let mut instruction_data = poll_instruction(1, 1);
+    instruction_data.push(1);
+
+    let result = process(&program_id, &mut accounts, &instruction_data);
+
+    assert_eq!(result, Err(ProgramError::from(PollError::VoterNotSigner)));
+    //voter pda was never created
+    assert!(accounts[1].data.is_empty());
+    assert_eq!(accounts[1].lamports, 0);
+    assert_eq!(accounts[1].owner, system_program::id());
+    assert_eq!(accounts[2].lamports, 1_000_000);
+}
@@ -4,7 +4,7 @@ import adminKey from '../../keys/admin.json';
 import accountKey1 from '../../keys/key1.json';
 import accountKey2 from '../../keys/key2.json';
 import accountKey3 from '../../keys/key3.json';
-import { commitment, OPTION_COUNT, OPTION_SIZE, Poll, PollCount, PollOption, PollVoter, TITLE_LENGTH } from './solutil';
+import { commitment, OPTION_SIZE, Poll, PollCount, PollOption, PollVoter, Proposal, ProposalInstruction, TITLE_LENGTH, VETO_KEYS } from './solutil';
 
 export enum Users {
     Admin,
@@ -118,8 +118,8 @@ export const getPollCountAccount = (accountData: AccountInfo<Buffer> | null): Po
     if (accountData != null) {
         const data = accountData.data;
         const isInitialized = new BN(data.slice(0, 1), "le").toNumber() === 0 ? false : true;
-        const count = new BN(data.slice(1, 2), "le").toNumber();
-        const bump = new BN(data.slice(2, 3), "le").toNumber();
+        const count = new BN(data.slice(1, 9), "le").toNumber();
+        const bump = new BN(data.slice(9, 10), "le").toNumber();
         const acc: PollCount = { isInitialized: isInitialized, count: count, bump: bump };
         console.log(acc);
         return acc;
@@ -131,10 +131,18 @@ export const getVoterPollAccount = (accountData: AccountInfo<Buffer> | null): Po
     if (accountData != null) {
         const data = accountData.data;
         const isInitialized = new BN(data.slice(0, 1), "le").toNumber() === 0 ? false : true;
-        const pollId = new BN(data.slice(1, 2), "le").toNumber();
-        const optionSelected = new BN(data.slice(2, 3), "le").toNumber();
-        const bump = new BN(data.slice(3, 4), "le").toNumber();
-        const acc: PollVoter = { isInitialized, pollId, optionSelected, bump };
+        const pollId = new BN(data.slice(1, 9), "le").toNumber();
+        const optionSelected = new BN(data.slice(9, 10), "le").toNumber();
+        const bump = new BN(data.slice(10, 11), "le").toNumber();
+        const selections = new BN(data.slice(11, 19), "le");
+        const ballot = new BN(data.slice(19, 23), "le").toNumber();
+        const weight = new BN(data.slice(23, 31), "le");
+        const commitment = data.slice(31, 63);
+        const payer = new PublicKey(data.slice(63, 95));
+        const allocation: number[] = [];
+        for (let i = 95; i + 2 <= data.length; i += 2)
+            allocation.push(data.readUInt16LE(i));
+        const acc: PollVoter = { isInitialized, pollId, optionSelected, bump, selections, ballot, weight, commitment, payer, allocation };
         return acc;
     }
     return null;
@@ -146,18 +154,88 @@ export const getPollAccount = (accountData: AccountInfo<Buffer> | null): Poll |
         let size = 0;
         const isInitialized = new BN(data.slice(size, size + 1), "le").toNumber() === 0 ? false : true;
         size += 1;
-        const id = new BN(data.slice(size, size + 1), "le").toNumber();
-        size += 1;
+        const creator = new PublicKey(data.slice(size, size + 32));
+        size += 32;
+        const id = new BN(data.slice(size, size + 8), "le").toNumber();
+        size += 8;
         let title = data.slice(size, size + TITLE_LENGTH).toString();
         size += TITLE_LENGTH;
         const titleLength = new BN(data.slice(size, size + 1), "le").toNumber();
         title = title.substring(0, titleLength);
         size += 1;
-        let optionsBuffer: Buffer = data.slice(size, size + OPTION_SIZE * OPTION_COUNT);
-        size += OPTION_SIZE * OPTION_COUNT;
+        const bump = new BN(data.slice(size, size + 1), "le").toNumber();
+        size += 1;
+        const startTs = new BN(data.slice(size, size + 8), "le").toNumber();
+        size += 8;
+        const endTs = new BN(data.slice(size, size + 8), "le").toNumber();
+        size += 8;
+        const allowRevote = new BN(data.slice(size, size + 1), "le").toNumber() === 0 ? false : true;
+        size += 1;
+        const status = new BN(data.slice(size, size + 1), "le").toNumber();
+        size += 1;
+        const winner = new BN(data.slice(size, size + 1), "le").toNumber();
+        size += 1;
+        const voteMode = new BN(data.slice(size, size + 1), "le").toNumber();
+        size += 1;
+        const maxSelections = new BN(data.slice(size, size + 1), "le").toNumber();
+        size += 1;
+        const voteMint = new PublicKey(data.slice(size, size + 32));
+        size += 32;
+        const lockTokens = new BN(data.slice(size, size + 1), "le").toNumber() === 0 ? false : true;
+        size += 1;
+        const credits = new BN(data.slice(size, size + 8), "le");
+        size += 8;
+        const allowlistRoot = data.slice(size, size + 32);
+        size += 32;
+        const nftCollection = new PublicKey(data.slice(size, size + 32));
+        size += 32;
+        const revealEndTs = new BN(data.slice(size, size + 8), "le").toNumber();
+        size += 8;
+        const unrevealed = new BN(data.slice(size, size + 8), "le").toNumber();
+        size += 8;
+        const quorum = new BN(data.slice(size, size + 8), "le");
+        size += 8;
+        const threshold = new BN(data.slice(size, size + 2), "le").toNumber();
+        size += 2;
+        const passOption = new BN(data.slice(size, size + 1), "le").toNumber();
+        size += 1;
+        const votesCast = new BN(data.slice(size, size + 8), "le");
+        size += 8;
+        const outcome = new BN(data.slice(size, size + 1), "le").toNumber();
+        size += 1;
+        const timelock = new BN(data.slice(size, size + 8), "le").toNumber();
+        size += 8;
+        const council = new PublicKey(data.slice(size, size + 32));
+        size += 32;
+        const proposalState = new BN(data.slice(size, size + 1), "le").toNumber();
+        size += 1;
+        const executeAfter = new BN(data.slice(size, size + 8), "le").toNumber();
+        size += 8;
+        const spendRecipient = new PublicKey(data.slice(size, size + 32));
+        size += 32;
+        const spendMint = new PublicKey(data.slice(size, size + 32));
+        size += 32;
+        const spendAmount = new BN(data.slice(size, size + 8), "le");
+        size += 8;
+        const vetoThreshold = new BN(data.slice(size, size + 1), "le").toNumber();
+        size += 1;
+        //unused veto keys are default
+        const vetoKeys: PublicKey[] = [];
+        for (let i = 0; i < VETO_KEYS; i++) {
+            const key = new PublicKey(data.slice(size, size + 32));
+            if (!key.equals(PublicKey.default))
+                vetoKeys.push(key);
+            size += 32;
+        }
+        const allowAbstain = data[size++] !== 0;
+        const allowNota = data[size++] !== 0;
+        const abstainVotes = new BN(data.slice(size, size + 8), "le");
+        size += 8;
+        const notaVotes = new BN(data.slice(size, size + 8), "le");
+        size += 8;
         const options_count = new BN(data.slice(size, size + 1), "le").toNumber();
         size += 1;
-        const bump = new BN(data.slice(size, size + 1), "le").toNumber();
+        let optionsBuffer: Buffer = data.slice(size, size + OPTION_SIZE * options_count);
 
 
         let op_size = 0;
@@ -174,7 +252,7 @@ export const getPollAccount = (accountData: AccountInfo<Buffer> | null): Poll |
             options.push(option);
         }
 
-        const poll: Poll = { id: id, bump: bump, isInitialized: isInitialized, options: options, optionsLength: options_count, title: title, titleLength: titleLength };
+        const poll: Poll = { id: id, creator: creator, bump: bump, isInitialized: isInitialized, options: options, optionsLength: options_count, title: title, titleLength: titleLength, startTs: startTs, endTs: endTs, allowRevote: allowRevote, status: status, winner: winner, voteMode: voteMode, maxSelections: maxSelections, voteMint: voteMint, lockTokens: lockTokens, credits: credits, allowlistRoot: allowlistRoot, nftCollection: nftCollection, revealEndTs: revealEndTs, unrevealed: unrevealed, quorum: quorum, threshold: threshold, passOption: passOption, votesCast: votesCast, outcome: outcome, timelock: timelock, council: council, proposalState: proposalState, executeAfter: executeAfter, spendRecipient: spendRecipient, spendMint: spendMint, spendAmount: spendAmount, vetoThreshold: vetoThreshold, vetoKeys: vetoKeys, allowAbstain: allowAbstain, allowNota: allowNota, abstainVotes: abstainVotes, notaVotes: notaVotes };
         console.log(poll);
         return poll;
     }
@@ -182,6 +260,53 @@ export const getPollAccount = (accountData: AccountInfo<Buffer> | null): Poll |
 }
 
 
+//token account of the owner holding the most of mint, used as voting weight
+//proposal accounts are borsh encoded: u32 lengths before the instructions, their account metas and data
+export const getProposalAccount = (accountData: AccountInfo<Buffer> | null): Proposal | null => {
+    if (accountData != null) {
+        const data = accountData.data;
+        let size = 0;
+        const isInitialized = data[size++] !== 0;
+        const pollId = new BN(data.slice(size, size + 8), "le").toNumber();
+        size += 8;
+        const bump = data[size++];
+        const instructions: ProposalInstruction[] = [];
+        const instructionsCount = data.readUInt32LE(size);
+        size += 4;
+        for (let i = 0; i < instructionsCount; i++) {
+            const programId = new PublicKey(data.slice(size, size + 32));
+            size += 32;
+            const keys = [];
+            const keysCount = data.readUInt32LE(size);
+            size += 4;
+            for (let j = 0; j < keysCount; j++) {
+                keys.push({ pubkey: new PublicKey(data.slice(size, size + 32)), isSigner: data[size + 32] !== 0, isWritable: data[size + 33] !== 0 });
+                size += 34;
+            }
+            const dataLength = data.readUInt32LE(size);
+            size += 4;
+            instructions.push({ programId, keys, data: data.slice(size, size + dataLength) });
+            size += dataLength;
+        }
+        return { isInitialized, pollId, bump, instructions };
+    }
+    return null;
+}
+
+export const getVoterTokenAccount = async (connection: Connection, owner: PublicKey, mint: PublicKey): Promise<PublicKey | undefined> => {
+    const accounts = await connection.getParsedTokenAccountsByOwner(owner, { mint: mint }, commitment);
+    let best: PublicKey | undefined;
+    let bestAmount = new BN(0);
+    for (const account of accounts.value) {
+        const amount = new BN(account.account.data.parsed.info.tokenAmount.amount);
+        if (amount.gt(bestAmount)) {
+            best = account.pubkey;
+            bestAmount = amount;
+        }
+    }
+    return best;
+}
+
 export function setAccountUpdateCallback(publicKey: PublicKey,
     callback: AccountChangeCallback,
     connection: Connection): void {
@@ -1,18 +1,33 @@
-import { Connection, LAMPORTS_PER_SOL, PublicKey } from "@solana/web3.js";
+import { AccountMeta, Connection, LAMPORTS_PER_SOL, PublicKey } from "@solana/web3.js";
 import { PhantomProvider } from "./phantom";
 import { Buffer } from 'buffer';
+import BN from 'bn.js';
 const BufferLayout = require("buffer-layout");
 export const cluster = "http://localhost:8899";
 export const commitment = "confirmed";
 export const connection = new Connection(cluster, commitment);
 export const poll_count_seed = "PollCount";
 export const poll_seed = "Poll";
+export const ballot_box_seed = "Ballots";
+export const escrow_seed = "Escrow";
+export const delegation_seed = "Delegation";
+export const proposal_seed = "Proposal";
+export const governance_seed = "Governance";
+export const treasury_seed = "Treasury";
+export const governance_config_seed = "GovernanceConfig";
+export const TOKEN_PROGRAM_ID = new PublicKey("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+export const TOKEN_METADATA_PROGRAM_ID = new PublicKey("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
 
 
 export const TITLE_LENGTH = 100;
 export const OPTION_LENGTH = 50;
-export const OPTION_COUNT = 4;
+export const MIN_OPTIONS = 2;
+export const MAX_OPTIONS = 64;
 export const OPTION_SIZE = (OPTION_LENGTH + 1 + 1 + 8);
+export const VETO_KEYS = 5;
+//built-in choices polls can enable, voted like options
+export const ABSTAIN_OPTION_ID = 255;
+export const NOTA_OPTION_ID = 254;
 
 export const getProvider = (): PhantomProvider | undefined => {
     if ("solana" in window) {
@@ -49,10 +64,118 @@ export const getBalance = async (accountKey: PublicKey): Promise<Number> => {
     return value
 }
 
-export const getPollCountSeedArray = (): Buffer[] => {
-    return [Buffer.from(poll_count_seed)]
+export const getPollCountSeedArray = (creator: PublicKey): Buffer[] => {
+    return [Buffer.from(poll_count_seed), creator.toBuffer()]
 }
 
+//polls up to 255 were created with a single byte id seed
+export const getPollIdSeed = (pollId: number): Buffer => {
+    if (pollId <= 255)
+        return Buffer.from([pollId]);
+    return new BN(pollId).toArrayLike(Buffer, "le", 8);
+}
+
+//polls are seeded by creator and index, polls from the old global namespace have no creator
+export const getPollSeedArray = (creator: PublicKey, pollId: number): Buffer[] => {
+    if (creator.equals(PublicKey.default))
+        return [Buffer.from(poll_seed), getPollIdSeed(pollId)];
+    return [Buffer.from(poll_seed), creator.toBuffer(), new BN(pollId).toArrayLike(Buffer, "le", 8)];
+}
+
+//ranked choice polls keep their ballots in a ballot box seeded by the poll's seeds and bump
+export const getBallotBoxSeedArray = (creator: PublicKey, pollId: number, pollBump: number): Buffer[] => {
+    return [...getPollSeedArray(creator, pollId), Buffer.from([pollBump]), Buffer.from(ballot_box_seed)];
+}
+
+//metadata account of an nft
+export const getNftMetadataSeedArray = (mint: PublicKey): Buffer[] => {
+    return [Buffer.from("metadata"), TOKEN_METADATA_PROGRAM_ID.toBuffer(), mint.toBuffer()];
+}
+
+//tokens locked by a voter are held in an escrow seeded by the voter pda's seeds
+export const getEscrowSeedArray = (creator: PublicKey, pollId: number, pollBump: number, voter: PublicKey): Buffer[] => {
+    return [...getPollSeedArray(creator, pollId), Buffer.from([pollBump]), voter.toBuffer(), Buffer.from(escrow_seed)];
+}
+
+//instructions an executable poll runs once it passes
+export const getProposalSeedArray = (creator: PublicKey, pollId: number, pollBump: number): Buffer[] => {
+    return [...getPollSeedArray(creator, pollId), Buffer.from([pollBump]), Buffer.from(proposal_seed)];
+}
+
+//governance pda signing the passed proposals of a creator
+export const getGovernanceSeedArray = (creator: PublicKey): Buffer[] => {
+    return [Buffer.from(governance_seed), creator.toBuffer()];
+}
+
+//minimums every executable poll of a creator has to meet, set once
+export const getGovernanceConfigSeedArray = (creator: PublicKey): Buffer[] => {
+    return [Buffer.from(governance_config_seed), creator.toBuffer()];
+}
+
+//treasury pda holding the lamports spend polls of a creator pay out
+export const getTreasurySeedArray = (creator: PublicKey): Buffer[] => {
+    return [Buffer.from(treasury_seed), creator.toBuffer()];
+}
+
+//treasury token account of a mint, its authority is the treasury pda
+export const getTreasuryTokenSeedArray = (creator: PublicKey, mint: PublicKey): Buffer[] => {
+    return [...getTreasurySeedArray(creator), mint.toBuffer()];
+}
+
+//delegations are seeded by the delegator and the poll account, a delegation for every poll uses the default key
+export const getDelegationSeedArray = (delegator: PublicKey, poll: PublicKey = PublicKey.default): Buffer[] => {
+    return [Buffer.from(delegation_seed), delegator.toBuffer(), poll.toBuffer()];
+}
+
+const sha256 = async (...data: Buffer[]): Promise<Buffer> => {
+    return Buffer.from(await crypto.subtle.digest("SHA-256", Buffer.concat(data)));
+}
+
+//ballot committed to on commit-reveal polls, revealed later with the same option id and salt
+export const getVoteCommitment = async (optionId: number, salt: Buffer): Promise<Buffer> => {
+    return await sha256(Buffer.from([optionId]), salt);
+}
+
+//allowlist merkle tree, leaves are the hashed voter keys and pairs are hashed in sorted order,
+//returns the layers from the leaves up to the root
+const getAllowlistLayers = async (voters: PublicKey[]): Promise<Buffer[][]> => {
+    let layer = await Promise.all(voters.map(voter => sha256(voter.toBuffer())));
+    const layers = [layer];
+    while (layer.length > 1) {
+        const next: Buffer[] = [];
+        for (let i = 0; i < layer.length; i += 2) {
+            if (i + 1 === layer.length)
+                next.push(layer[i]);
+            else if (Buffer.compare(layer[i], layer[i + 1]) <= 0)
+                next.push(await sha256(layer[i], layer[i + 1]));
+            else
+                next.push(await sha256(layer[i + 1], layer[i]));
+        }
+        layers.push(next);
+        layer = next;
+    }
+    return layers;
+}
+
+export const getAllowlistRoot = async (voters: PublicKey[]): Promise<Buffer> => {
+    const layers = await getAllowlistLayers(voters);
+    return layers[layers.length - 1][0] || Buffer.alloc(32);
+}
+
+//sibling hashes from the voter's leaf up to the root, undefined if the voter is not on the list
+export const getAllowlistProof = async (voters: PublicKey[], voter: PublicKey): Promise<Buffer[] | undefined> => {
+    let idx = voters.findIndex(key => key.equals(voter));
+    if (idx < 0)
+        return undefined;
+    const proof: Buffer[] = [];
+    for (const layer of (await getAllowlistLayers(voters)).slice(0, -1)) {
+        const sibling = idx % 2 === 0 ? idx + 1 : idx - 1;
+        if (sibling < layer.length)
+            proof.push(layer[sibling]);
+        idx = Math.floor(idx / 2);
+    }
+    return proof;
+}
 
 export const uint64 = (property = "uint64") => {
     return BufferLayout.blob(8, property);
@@ -78,26 +201,65 @@ export const VOTER_ACCOUNT = BufferLayout.struct([
 
 export const POLL_COUNT_ACCOUNT = BufferLayout.struct([
     BufferLayout.u8("isInitialized"),
-    BufferLayout.u8("poll_count")
+    uint64("poll_count"),
+    BufferLayout.u8("bump")
 ])
 
 export const POLL_ACCOUNT = BufferLayout.struct([
     BufferLayout.u8("isInitialized"),
-    BufferLayout.u8("id"),
+    string_len("creator", 32),
+    uint64("id"),
     string_len("title", 100),
     BufferLayout.u8("title_length"),
-    string_len("options", 60 * 4),
-    BufferLayout.u8("options_length"),
     BufferLayout.u8("bump"),
+    uint64("start_ts"),
+    uint64("end_ts"),
+    BufferLayout.u8("allow_revote"),
+    BufferLayout.u8("status"),
+    BufferLayout.u8("winner"),
+    BufferLayout.u8("vote_mode"),
+    BufferLayout.u8("max_selections"),
+    string_len("vote_mint", 32),
+    BufferLayout.u8("lock_tokens"),
+    uint64("credits"),
+    string_len("allowlist_root", 32),
+    string_len("nft_collection", 32),
+    uint64("reveal_end_ts"),
+    uint64("unrevealed"),
+    uint64("quorum"),
+    BufferLayout.u16("threshold"),
+    BufferLayout.u8("pass_option"),
+    uint64("votes_cast"),
+    BufferLayout.u8("outcome"),
+    uint64("timelock"),
+    string_len("council", 32),
+    BufferLayout.u8("proposal_state"),
+    uint64("execute_after"),
+    string_len("spend_recipient", 32),
+    string_len("spend_mint", 32),
+    uint64("spend_amount"),
+    BufferLayout.u8("veto_threshold"),
+    string_len("veto_keys", 32 * VETO_KEYS),
+    BufferLayout.u8("allow_abstain"),
+    BufferLayout.u8("allow_nota"),
+    uint64("abstain_votes"),
+    uint64("nota_votes"),
+    BufferLayout.u8("options_length"),
+    //followed by options_length options of OPTION_SIZE
 
 ])
 
 
 export const POLL_VOTER_ACCOUNT = BufferLayout.struct([
     BufferLayout.u8("isInitialized"),
-    BufferLayout.u8("id"),
+    uint64("id"),
     BufferLayout.u8("selected"),
     BufferLayout.u8("bump"),
+    uint64("selections"),
+    BufferLayout.u32("ballot"),
+    uint64("weight"),
+    string_len("commitment", 32),
+    //quadratic polls only: votes (u16) for each option
 ])
 
 export interface PollCount {
@@ -110,7 +272,19 @@ export interface PollVoter {
     isInitialized: boolean,
     pollId: number,
     optionSelected: number,
-    bump: number
+    bump: number,
+    //bitmask of the selected options, bit 0 is option 1
+    selections: BN,
+    //slot of a ranked ballot in the ballot box
+    ballot: number,
+    //votes the voter added to each selection
+    weight: BN,
+    //hash of the option id and salt of a ballot not revealed yet, all 0 once revealed
+    commitment: Buffer,
+    //paid the rent of the voter pda, the voter or the delegate that voted for them
+    payer: PublicKey,
+    //votes for each option on quadratic polls, k votes cost k² credits
+    allocation: number[]
 }
 
 export interface PollOption {
@@ -122,10 +296,103 @@ export interface PollOption {
 
 export interface Poll {
     isInitialized: boolean,
+    creator: PublicKey,
     id: number,
     title: string,
     titleLength: number,
     options: PollOption[],
     optionsLength: number,
-    bump: number
+    bump: number,
+    startTs: number,
+    endTs: number,
+    allowRevote: boolean,
+    status: PollStatus,
+    winner: number,
+    voteMode: VoteMode,
+    maxSelections: number,
+    //default for one vote per voter, otherwise votes are weighted by the balance of this token
+    voteMint: PublicKey,
+    //voted tokens are escrowed until voting is over
+    lockTokens: boolean,
+    //voice credits of every voter on quadratic polls
+    credits: BN,
+    //merkle root of the voters allowed to vote, all 0 when anyone can vote
+    allowlistRoot: Buffer,
+    //only holders of a verified nft of this collection can vote, default when not nft gated
+    nftCollection: PublicKey,
+    //ballots are committed until endTs and revealed until revealEndTs, 0 for plain ballots
+    revealEndTs: number,
+    //committed ballots not revealed yet
+    unrevealed: number,
+    //votes (by weight) that have to be cast for the poll to be decided
+    quorum: BN,
+    //basis points of the votes cast the pass option has to exceed
+    threshold: number,
+    //option id the threshold applies to, 0 for the winner
+    passOption: number,
+    //weight of the ballots counted, each ballot once
+    votesCast: BN,
+    outcome: PollOutcome,
+    //seconds a passed proposal waits before it can be executed
+    timelock: number,
+    //can cancel queued proposals besides the creator, default for none
+    council: PublicKey,
+    proposalState: ProposalState,
+    //queued proposals can be executed from this time on
+    executeAfter: number,
+    //spend polls pay spendAmount of spendMint (default for lamports) out of the creator's treasury
+    //to spendRecipient once they passed, spendAmount is 0 on other polls
+    spendRecipient: PublicKey,
+    spendMint: PublicKey,
+    spendAmount: BN,
+    //signatures of veto keys needed to veto the poll once it passed, 0 without veto authority
+    vetoThreshold: number,
+    vetoKeys: PublicKey[],
+    //abstentions only count toward the quorum, none of the above counts against every option
+    allowAbstain: boolean,
+    allowNota: boolean,
+    abstainVotes: BN,
+    notaVotes: BN
+}
+
+//instruction of an executable poll, the governance pda signs it when the proposal is executed
+export interface ProposalInstruction {
+    programId: PublicKey,
+    keys: AccountMeta[],
+    data: Buffer
+}
+
+export interface Proposal {
+    isInitialized: boolean,
+    pollId: number,
+    bump: number,
+    instructions: ProposalInstruction[]
+}
+
+export enum PollStatus {
+    Open,
+    Finalized
+}
+
+export enum PollOutcome {
+    Undecided,
+    Passed,
+    Failed,
+    QuorumNotMet,
+    Vetoed
+}
+
+export enum ProposalState {
+    None,
+    Pending,
+    Queued,
+    Executed,
+    Cancelled
+}
+
+export enum VoteMode {
+    Single,
+    Approval,
+    Ranked,
+    Quadratic
 }
\ No newline at end of file
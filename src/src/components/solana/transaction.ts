@@ -1,8 +1,11 @@
-import { Connection, PublicKey, SystemProgram, Transaction, TransactionInstruction } from "@solana/web3.js";
+import { AccountMeta, Connection, PublicKey, SystemProgram, SYSVAR_RENT_PUBKEY, Transaction, TransactionInstruction } from "@solana/web3.js";
 import { PhantomProvider } from "./phantom";
 import { Buffer } from 'buffer';
+import BN from 'bn.js';
+import { ProposalInstruction, TOKEN_PROGRAM_ID } from "./solutil";
 
-export const createPollInitInstruction = (feePayer: PublicKey, pollCountAccount: PublicKey, pollAccount: PublicKey, pid: PublicKey, instructionU8: Uint8Array) => {
+//ranked choice polls also create their ballot box
+export const createPollInitInstruction = (feePayer: PublicKey, pollCountAccount: PublicKey, pollAccount: PublicKey, pid: PublicKey, instructionU8: Uint8Array, ballotBoxAccount?: PublicKey, governanceConfigAccount?: PublicKey) => {
     const t = new TransactionInstruction(
         {
             keys: [
@@ -24,9 +27,12 @@ export const createPollInitInstruction = (feePayer: PublicKey, pollCountAccount:
                 },
                 {
                     pubkey: feePayer,
-                    isSigner: false,
-                    isWritable: false
-                }
+                    isSigner: true,
+                    isWritable: true
+                },
+                //spend polls are checked against the creator's governance config
+                ...optionalKeys(governanceConfigAccount, false),
+                ...optionalKeys(ballotBoxAccount)
             ],
             //instruction
             //data: Buffer.from(new Uint8Array([0, group])),
@@ -37,14 +43,18 @@ export const createPollInitInstruction = (feePayer: PublicKey, pollCountAccount:
     return t;
 }
 
-export const createPollInitInstructionData = (title_length: number, title: string, options_count: number, option_size: number[], options: string[]) => {
-    //title len (u8) + title (title length)+ options_count (u8) + options_size (options_count) + options (opsize ..)
+export const createPollInitInstructionData = (title_length: number, title: string, options_count: number, option_size: number[], options: string[], startTs: number, endTs: number, allowRevote: boolean, voteMode: number, maxSelections: number, voteMint: PublicKey, lockTokens: boolean, credits: number, allowlistRoot: Buffer, nftCollection: PublicKey, revealEndTs: number, quorum: number, threshold: number, passOption: number, timelock: number, council: PublicKey, spendRecipient: PublicKey, spendMint: PublicKey, spendAmount: BN, vetoThreshold: number, vetoKeys: PublicKey[], allowAbstain: boolean, allowNota: boolean) => {
+    //title len (u8) + title (title length)+ options_count (u8) + options_size (options_count) + options (opsize ..) + start ts (i64) + end ts (i64) + allow revote (u8)
+    //+ vote mode (u8) + max selections (u8) + vote mint (32) + lock tokens (u8) + credits (u64) + allowlist root (32) + nft collection (32) + reveal end ts (i64)
+    //+ quorum (u64) + threshold (u16) + pass option (u8) + timelock (i64) + council (32)
+    //+ spend recipient (32) + spend mint (32) + spend amount (u64) + veto threshold (u8) + veto keys count (u8) + veto keys (32 each)
+    //+ allow abstain (u8) + allow nota (u8)
     console.log("title_length", title_length);
     console.log("title", title);
     console.log("options_count", options_count);
     console.log("option_size", option_size);
     console.log("options", options);
-    var totalSize = 1 + 1 + title_length + 1 + options_count;
+    var totalSize = 1 + 1 + title_length + 1 + options_count + 8 + 8 + 1 + 1 + 1 + 32 + 1 + 8 + 32 + 32 + 8 + 8 + 2 + 1 + 8 + 32 + 32 + 32 + 8 + 1 + 1 + 32 * vetoKeys.length + 1 + 1;
     for (let i = 0; i < option_size.length; i++) {
         totalSize += option_size[i];
     }
@@ -76,12 +86,105 @@ export const createPollInitInstructionData = (title_length: number, title: strin
         }
     }
 
+    //voting window, 0 means no bound
+    uarray.set(new BN(startTs).toArrayLike(Buffer, "le", 8), counter);
+    counter += 8;
+    uarray.set(new BN(endTs).toArrayLike(Buffer, "le", 8), counter);
+    counter += 8;
+    uarray[counter++] = allowRevote ? 1 : 0;
+    uarray[counter++] = voteMode;
+    uarray[counter++] = maxSelections;
+    uarray.set(voteMint.toBuffer(), counter);
+    counter += 32;
+    uarray[counter++] = lockTokens ? 1 : 0;
+    uarray.set(new BN(credits).toArrayLike(Buffer, "le", 8), counter);
+    counter += 8;
+    uarray.set(allowlistRoot, counter);
+    counter += 32;
+    uarray.set(nftCollection.toBuffer(), counter);
+    counter += 32;
+    uarray.set(new BN(revealEndTs).toArrayLike(Buffer, "le", 8), counter);
+    counter += 8;
+    uarray.set(new BN(quorum).toArrayLike(Buffer, "le", 8), counter);
+    counter += 8;
+    uarray.set(new BN(threshold).toArrayLike(Buffer, "le", 2), counter);
+    counter += 2;
+    uarray[counter++] = passOption;
+    uarray.set(new BN(timelock).toArrayLike(Buffer, "le", 8), counter);
+    counter += 8;
+    uarray.set(council.toBuffer(), counter);
+    counter += 32;
+    uarray.set(spendRecipient.toBuffer(), counter);
+    counter += 32;
+    uarray.set(spendMint.toBuffer(), counter);
+    counter += 32;
+    uarray.set(spendAmount.toArrayLike(Buffer, "le", 8), counter);
+    counter += 8;
+    uarray[counter++] = vetoThreshold;
+    uarray[counter++] = vetoKeys.length;
+    for (const key of vetoKeys) {
+        uarray.set(key.toBuffer(), counter);
+        counter += 32;
+    }
+    uarray[counter++] = allowAbstain ? 1 : 0;
+    uarray[counter++] = allowNota ? 1 : 0;
+
     return uarray;
 
 }
 
 
-export const createPollVoteInstruction = (feePayer: PublicKey, pollAccount: PublicKey, voterAccount: PublicKey, pid: PublicKey, pollId: number, optionId: number) => {
+//accounts only some polls take (ballot box, voter nft account)
+const optionalKeys = (account?: PublicKey, isWritable: boolean = true) => {
+    if (!account)
+        return [];
+    return [{
+        pubkey: account,
+        isSigner: false,
+        isWritable: isWritable
+    }];
+}
+
+//allowlisted polls need the voter's merkle proof, a 0 byte separates it from option ids
+const proofData = (proof: Buffer[]) => {
+    return proof.length > 0 ? Buffer.concat([Buffer.from([0]), ...proof]) : Buffer.alloc(0);
+}
+
+//token weighted polls need the voter's token account of the vote mint,
+//polls locking tokens also need the mint and the voter's escrow
+export const getVoteTokenKeys = (tokenAccount?: PublicKey, lock?: { mint: PublicKey, escrow: PublicKey }): AccountMeta[] => {
+    if (!tokenAccount)
+        return [];
+    const keys = [{ pubkey: tokenAccount, isSigner: false, isWritable: !!lock }];
+    if (!lock)
+        return keys;
+    return [...keys,
+    { pubkey: lock.mint, isSigner: false, isWritable: false },
+    { pubkey: lock.escrow, isSigner: false, isWritable: true },
+    { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+    { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false }];
+}
+
+//nft gated polls need the voter's token account of the nft and the nft's metadata account
+export const getVoteNftKeys = (nftAccount: PublicKey, metadataAccount: PublicKey): AccountMeta[] => {
+    return [{ pubkey: nftAccount, isSigner: false, isWritable: false },
+    { pubkey: metadataAccount, isSigner: false, isWritable: false }];
+}
+
+//a delegate votes for a delegator with the delegation, the delegator's delegation for the poll
+//when the first covers every poll (it must not exist), the delegator's voter pda
+//and on token weighted polls the delegator's token account
+export const getVoteDelegationKeys = (delegationAccount: PublicKey, delegatorVoterAccount: PublicKey, pollDelegationAccount?: PublicKey, tokenAccount?: PublicKey): AccountMeta[] => {
+    const keys = [{ pubkey: delegationAccount, isSigner: false, isWritable: false }];
+    if (pollDelegationAccount)
+        keys.push({ pubkey: pollDelegationAccount, isSigner: false, isWritable: false });
+    keys.push({ pubkey: delegatorVoterAccount, isSigner: false, isWritable: true });
+    if (tokenAccount)
+        keys.push({ pubkey: tokenAccount, isSigner: false, isWritable: false });
+    return keys;
+}
+
+export const createPollVoteInstruction = (feePayer: PublicKey, pollAccount: PublicKey, voterAccount: PublicKey, pid: PublicKey, pollId: number, optionId: number, tokenKeys: AccountMeta[] = [], proof: Buffer[] = []) => {
     const t = new TransactionInstruction(
         {
             keys: [
@@ -98,17 +201,18 @@ export const createPollVoteInstruction = (feePayer: PublicKey, pollAccount: Publ
                 },
                 {
                     pubkey: feePayer,
-                    isSigner: false,
-                    isWritable: false
+                    isSigner: true,
+                    isWritable: true
                 },
                 {
                     pubkey: SystemProgram.programId,
                     isSigner: false,
                     isWritable: false
-                }
+                },
+                ...tokenKeys
             ],
             //instruction
-            data: Buffer.from(new Uint8Array([1, pollId, optionId])),
+            data: Buffer.concat([Buffer.from([1]), new BN(pollId).toArrayLike(Buffer, "le", 8), Buffer.from([optionId]), proofData(proof)]),
             //data: Buffer.from(instructionU8),
             programId: pid
         }
@@ -117,12 +221,12 @@ export const createPollVoteInstruction = (feePayer: PublicKey, pollAccount: Publ
 }
 
 export const createProposal = async (connection: Connection, from: PhantomProvider, pollCountKey: PublicKey, pollKey: PublicKey, programId: PublicKey, title: string,
-    options: string[]) => {
+    options: string[], startTs: number = 0, endTs: number = 0, allowRevote: boolean = false, voteMode: number = 0, maxSelections: number = 0, voteMint: PublicKey = PublicKey.default, lockTokens: boolean = false, credits: number = 0, allowlistRoot: Buffer = Buffer.alloc(32), nftCollection: PublicKey = PublicKey.default, revealEndTs: number = 0, quorum: number = 0, threshold: number = 0, passOption: number = 0, timelock: number = 0, council: PublicKey = PublicKey.default, spendRecipient: PublicKey = PublicKey.default, spendMint: PublicKey = PublicKey.default, spendAmount: BN = new BN(0), vetoThreshold: number = 0, vetoKeys: PublicKey[] = [], allowAbstain: boolean = false, allowNota: boolean = false, ballotBoxKey?: PublicKey, governanceConfigKey?: PublicKey) => {
     let optionsSize: number[] = [];
     for (let i = 0; i < options.length; i++)
         optionsSize.push(options[i].length);
-    const data = createPollInitInstructionData(title.length, title, options.length, optionsSize, options);
-    const ix = createPollInitInstruction(from!.publicKey!, pollCountKey, pollKey, programId, data);
+    const data = createPollInitInstructionData(title.length, title, options.length, optionsSize, options, startTs, endTs, allowRevote, voteMode, maxSelections, voteMint, lockTokens, credits, allowlistRoot, nftCollection, revealEndTs, quorum, threshold, passOption, timelock, council, spendRecipient, spendMint, spendAmount, vetoThreshold, vetoKeys, allowAbstain, allowNota);
+    const ix = createPollInitInstruction(from!.publicKey!, pollCountKey, pollKey, programId, data, ballotBoxKey, governanceConfigKey);
     let tx = new Transaction();
     tx.add(ix);
     tx.feePayer = await from!.publicKey!;
@@ -144,8 +248,8 @@ export const createProposal = async (connection: Connection, from: PhantomProvid
     return response
 }
 
-export const voteTransaction = async (connection: Connection, from: PhantomProvider, pollPublicKey: PublicKey, voterKey: PublicKey, programId: PublicKey, pollId: number, optionId: number) => {
-    const transferIx = createPollVoteInstruction(from!.publicKey!, pollPublicKey, voterKey, programId, pollId, optionId);
+export const voteTransaction = async (connection: Connection, from: PhantomProvider, pollPublicKey: PublicKey, voterKey: PublicKey, programId: PublicKey, pollId: number, optionId: number, tokenKeys: AccountMeta[] = [], proof: Buffer[] = []) => {
+    const transferIx = createPollVoteInstruction(from!.publicKey!, pollPublicKey, voterKey, programId, pollId, optionId, tokenKeys, proof);
     //signers.push(programAccountKey);
     let tx = new Transaction();
     tx.add(transferIx);
@@ -172,3 +276,734 @@ export const voteTransaction = async (connection: Connection, from: PhantomProvi
     return response
 }
 
+//commit-reveal polls take the commitment instead of an option id, see getVoteCommitment
+export const createCommitVoteInstruction = (feePayer: PublicKey, pollAccount: PublicKey, voterAccount: PublicKey, pid: PublicKey, pollId: number, commitment: Buffer, tokenKeys: AccountMeta[] = [], proof: Buffer[] = []) => {
+    return new TransactionInstruction(
+        {
+            keys: [
+                {
+                    pubkey: pollAccount,
+                    isSigner: false,
+                    isWritable: true
+                },
+                {
+                    pubkey: voterAccount,
+                    isSigner: false,
+                    isWritable: true
+                },
+                {
+                    pubkey: feePayer,
+                    isSigner: true,
+                    isWritable: true
+                },
+                {
+                    pubkey: SystemProgram.programId,
+                    isSigner: false,
+                    isWritable: false
+                },
+                ...tokenKeys
+            ],
+            data: Buffer.concat([Buffer.from([1]), new BN(pollId).toArrayLike(Buffer, "le", 8), Buffer.from([0]), commitment, ...proof]),
+            programId: pid
+        }
+    );
+}
+
+export const commitVoteTransaction = async (connection: Connection, from: PhantomProvider, pollPublicKey: PublicKey, voterKey: PublicKey, programId: PublicKey, pollId: number, commitment: Buffer, tokenKeys: AccountMeta[] = [], proof: Buffer[] = []) => {
+    const ix = createCommitVoteInstruction(from!.publicKey!, pollPublicKey, voterKey, programId, pollId, commitment, tokenKeys, proof);
+    return await sendInstruction(connection, from, ix);
+}
+
+//voter opens their committed ballot once voting has ended
+export const createRevealVoteInstruction = (voter: PublicKey, pollAccount: PublicKey, voterAccount: PublicKey, pid: PublicKey, pollId: number, optionId: number, salt: Buffer, nftAccount?: PublicKey) => {
+    return new TransactionInstruction(
+        {
+            keys: [
+                {
+                    pubkey: pollAccount,
+                    isSigner: false,
+                    isWritable: true
+                },
+                {
+                    pubkey: voterAccount,
+                    isSigner: false,
+                    isWritable: true
+                },
+                {
+                    pubkey: voter,
+                    isSigner: true,
+                    isWritable: false
+                },
+                ...optionalKeys(nftAccount, false)
+            ],
+            data: Buffer.concat([Buffer.from([13]), new BN(pollId).toArrayLike(Buffer, "le", 8), Buffer.from([optionId]), salt]),
+            programId: pid
+        }
+    );
+}
+
+export const revealVoteTransaction = async (connection: Connection, from: PhantomProvider, pollPublicKey: PublicKey, voterKey: PublicKey, programId: PublicKey, pollId: number, optionId: number, salt: Buffer, nftAccount?: PublicKey) => {
+    const ix = createRevealVoteInstruction(from!.publicKey!, pollPublicKey, voterKey, programId, pollId, optionId, salt, nftAccount);
+    return await sendInstruction(connection, from, ix);
+}
+
+
+
+//sign with the wallet, send and confirm a single instruction
+const sendInstruction = async (connection: Connection, from: PhantomProvider, ix: TransactionInstruction) => {
+    let tx = new Transaction();
+    tx.add(ix);
+    tx.feePayer = from!.publicKey!;
+    let blockhashObj = await connection.getLatestBlockhash();
+    tx.recentBlockhash = blockhashObj.blockhash;
+    let signed = await from!.signTransaction(tx);
+    let signature = await connection.sendRawTransaction(signed.serialize());
+    const response = await connection.confirmTransaction(signature);
+    console.log(response.value);
+    return response
+}
+
+export const createClosePollInstruction = (creator: PublicKey, pollAccount: PublicKey, pid: PublicKey, pollId: number) => {
+    return new TransactionInstruction(
+        {
+            keys: [
+                {
+                    pubkey: pollAccount,
+                    isSigner: false,
+                    isWritable: true
+                },
+                {
+                    pubkey: creator,
+                    isSigner: true,
+                    isWritable: false
+                }
+            ],
+            data: Buffer.concat([Buffer.from([3]), new BN(pollId).toArrayLike(Buffer, "le", 8)]),
+            programId: pid
+        }
+    );
+}
+
+export const closePollTransaction = async (connection: Connection, from: PhantomProvider, pollPublicKey: PublicKey, programId: PublicKey, pollId: number) => {
+    const ix = createClosePollInstruction(from!.publicKey!, pollPublicKey, programId, pollId);
+    return await sendInstruction(connection, from, ix);
+}
+
+//nft gated polls need the voter's token account of the nft the vote was cast with,
+//the rent goes back to the rent payer stored in the voter pda
+export const createCloseVoterAccountInstruction = (voter: PublicKey, pollAccount: PublicKey, voterAccount: PublicKey, rentPayer: PublicKey, creator: PublicKey, pid: PublicKey, pollId: number, nftAccount?: PublicKey) => {
+    return new TransactionInstruction(
+        {
+            keys: [
+                {
+                    pubkey: pollAccount,
+                    isSigner: false,
+                    isWritable: false
+                },
+                {
+                    pubkey: voterAccount,
+                    isSigner: false,
+                    isWritable: true
+                },
+                {
+                    pubkey: voter,
+                    isSigner: true,
+                    isWritable: true
+                },
+                {
+                    pubkey: rentPayer,
+                    isSigner: false,
+                    isWritable: true
+                },
+                {
+                    pubkey: creator,
+                    isSigner: false,
+                    isWritable: false
+                },
+                ...optionalKeys(nftAccount, false)
+            ],
+            data: Buffer.concat([Buffer.from([4]), new BN(pollId).toArrayLike(Buffer, "le", 8)]),
+            programId: pid
+        }
+    );
+}
+
+export const closeVoterAccountTransaction = async (connection: Connection, from: PhantomProvider, pollPublicKey: PublicKey, voterKey: PublicKey, rentPayer: PublicKey, creator: PublicKey, programId: PublicKey, pollId: number, nftAccount?: PublicKey) => {
+    const ix = createCloseVoterAccountInstruction(from!.publicKey!, pollPublicKey, voterKey, rentPayer, creator, programId, pollId, nftAccount);
+    return await sendInstruction(connection, from, ix);
+}
+
+export const createClosePollAccountInstruction = (creator: PublicKey, pollAccount: PublicKey, pid: PublicKey, pollId: number, ballotBoxAccount?: PublicKey) => {
+    return new TransactionInstruction(
+        {
+            keys: [
+                {
+                    pubkey: pollAccount,
+                    isSigner: false,
+                    isWritable: true
+                },
+                {
+                    pubkey: creator,
+                    isSigner: true,
+                    isWritable: true
+                },
+                ...optionalKeys(ballotBoxAccount)
+            ],
+            data: Buffer.concat([Buffer.from([5]), new BN(pollId).toArrayLike(Buffer, "le", 8)]),
+            programId: pid
+        }
+    );
+}
+
+export const closePollAccountTransaction = async (connection: Connection, from: PhantomProvider, pollPublicKey: PublicKey, programId: PublicKey, pollId: number, ballotBoxKey?: PublicKey) => {
+    const ix = createClosePollAccountInstruction(from!.publicKey!, pollPublicKey, programId, pollId, ballotBoxKey);
+    return await sendInstruction(connection, from, ix);
+}
+
+//the rent goes back to the rent payer stored in the voter pda
+export const createRetractVoteInstruction = (voter: PublicKey, pollAccount: PublicKey, voterAccount: PublicKey, rentPayer: PublicKey, pid: PublicKey, pollId: number, ballotBoxAccount?: PublicKey, nftAccount?: PublicKey) => {
+    return new TransactionInstruction(
+        {
+            keys: [
+                {
+                    pubkey: pollAccount,
+                    isSigner: false,
+                    isWritable: true
+                },
+                {
+                    pubkey: voterAccount,
+                    isSigner: false,
+                    isWritable: true
+                },
+                {
+                    pubkey: voter,
+                    isSigner: true,
+                    isWritable: true
+                },
+                {
+                    pubkey: rentPayer,
+                    isSigner: false,
+                    isWritable: true
+                },
+                ...optionalKeys(ballotBoxAccount),
+                ...optionalKeys(nftAccount, false)
+            ],
+            data: Buffer.concat([Buffer.from([6]), new BN(pollId).toArrayLike(Buffer, "le", 8)]),
+            programId: pid
+        }
+    );
+}
+
+export const retractVoteTransaction = async (connection: Connection, from: PhantomProvider, pollPublicKey: PublicKey, voterKey: PublicKey, rentPayer: PublicKey, programId: PublicKey, pollId: number, ballotBoxKey?: PublicKey, nftAccount?: PublicKey) => {
+    const ix = createRetractVoteInstruction(from!.publicKey!, pollPublicKey, voterKey, rentPayer, programId, pollId, ballotBoxKey, nftAccount);
+    return await sendInstruction(connection, from, ix);
+}
+
+//selections is a bitmask of the approved options, bit 0 is option 1
+export const createApprovalVoteInstruction = (feePayer: PublicKey, pollAccount: PublicKey, voterAccount: PublicKey, pid: PublicKey, pollId: number, selections: BN, tokenKeys: AccountMeta[] = [], proof: Buffer[] = []) => {
+    return new TransactionInstruction(
+        {
+            keys: [
+                {
+                    pubkey: pollAccount,
+                    isSigner: false,
+                    isWritable: true
+                },
+                {
+                    pubkey: voterAccount,
+                    isSigner: false,
+                    isWritable: true
+                },
+                {
+                    pubkey: feePayer,
+                    isSigner: true,
+                    isWritable: true
+                },
+                {
+                    pubkey: SystemProgram.programId,
+                    isSigner: false,
+                    isWritable: false
+                },
+                ...tokenKeys
+            ],
+            data: Buffer.concat([Buffer.from([7]), new BN(pollId).toArrayLike(Buffer, "le", 8), selections.toArrayLike(Buffer, "le", 8), ...proof]),
+            programId: pid
+        }
+    );
+}
+
+export const approvalVoteTransaction = async (connection: Connection, from: PhantomProvider, pollPublicKey: PublicKey, voterKey: PublicKey, programId: PublicKey, pollId: number, selections: BN, tokenKeys: AccountMeta[] = [], proof: Buffer[] = []) => {
+    const ix = createApprovalVoteInstruction(from!.publicKey!, pollPublicKey, voterKey, programId, pollId, selections, tokenKeys, proof);
+    return await sendInstruction(connection, from, ix);
+}
+
+//optionIds are the ranked options by preference
+export const createRankedVoteInstruction = (feePayer: PublicKey, pollAccount: PublicKey, voterAccount: PublicKey, ballotBoxAccount: PublicKey, pid: PublicKey, pollId: number, optionIds: number[], tokenKeys: AccountMeta[] = [], proof: Buffer[] = []) => {
+    return new TransactionInstruction(
+        {
+            keys: [
+                {
+                    pubkey: pollAccount,
+                    isSigner: false,
+                    isWritable: true
+                },
+                {
+                    pubkey: voterAccount,
+                    isSigner: false,
+                    isWritable: true
+                },
+                {
+                    pubkey: feePayer,
+                    isSigner: true,
+                    isWritable: true
+                },
+                {
+                    pubkey: SystemProgram.programId,
+                    isSigner: false,
+                    isWritable: false
+                },
+                ...optionalKeys(ballotBoxAccount),
+                ...tokenKeys
+            ],
+            data: Buffer.concat([Buffer.from([1]), new BN(pollId).toArrayLike(Buffer, "le", 8), Buffer.from(optionIds), proofData(proof)]),
+            programId: pid
+        }
+    );
+}
+
+export const rankedVoteTransaction = async (connection: Connection, from: PhantomProvider, pollPublicKey: PublicKey, voterKey: PublicKey, ballotBoxKey: PublicKey, programId: PublicKey, pollId: number, optionIds: number[], tokenKeys: AccountMeta[] = [], proof: Buffer[] = []) => {
+    const ix = createRankedVoteInstruction(from!.publicKey!, pollPublicKey, voterKey, ballotBoxKey, programId, pollId, optionIds, tokenKeys, proof);
+    return await sendInstruction(connection, from, ix);
+}
+
+//votes has the votes for each option in order (up to 255), k votes cost k² voice credits
+export const createQuadraticVoteInstruction = (feePayer: PublicKey, pollAccount: PublicKey, voterAccount: PublicKey, pid: PublicKey, pollId: number, votes: number[], tokenKeys: AccountMeta[] = [], proof: Buffer[] = []) => {
+    const votesData = Buffer.alloc(1 + 2 * votes.length);
+    votesData[0] = votes.length;
+    votes.forEach((count, i) => votesData.writeUInt16LE(count, 1 + 2 * i));
+    return new TransactionInstruction(
+        {
+            keys: [
+                {
+                    pubkey: pollAccount,
+                    isSigner: false,
+                    isWritable: true
+                },
+                {
+                    pubkey: voterAccount,
+                    isSigner: false,
+                    isWritable: true
+                },
+                {
+                    pubkey: feePayer,
+                    isSigner: true,
+                    isWritable: true
+                },
+                {
+                    pubkey: SystemProgram.programId,
+                    isSigner: false,
+                    isWritable: false
+                },
+                ...tokenKeys
+            ],
+            data: Buffer.concat([Buffer.from([10]), new BN(pollId).toArrayLike(Buffer, "le", 8), votesData, ...proof]),
+            programId: pid
+        }
+    );
+}
+
+export const quadraticVoteTransaction = async (connection: Connection, from: PhantomProvider, pollPublicKey: PublicKey, voterKey: PublicKey, programId: PublicKey, pollId: number, votes: number[], tokenKeys: AccountMeta[] = [], proof: Buffer[] = []) => {
+    const ix = createQuadraticVoteInstruction(from!.publicKey!, pollPublicKey, voterKey, programId, pollId, votes, tokenKeys, proof);
+    return await sendInstruction(connection, from, ix);
+}
+
+//anyone can run the instant runoff tally once voting is over
+export const createTallyRoundInstruction = (pollAccount: PublicKey, ballotBoxAccount: PublicKey, pid: PublicKey, pollId: number) => {
+    return new TransactionInstruction(
+        {
+            keys: [
+                {
+                    pubkey: pollAccount,
+                    isSigner: false,
+                    isWritable: true
+                },
+                {
+                    pubkey: ballotBoxAccount,
+                    isSigner: false,
+                    isWritable: true
+                }
+            ],
+            data: Buffer.concat([Buffer.from([8]), new BN(pollId).toArrayLike(Buffer, "le", 8)]),
+            programId: pid
+        }
+    );
+}
+
+export const tallyRoundTransaction = async (connection: Connection, from: PhantomProvider, pollPublicKey: PublicKey, ballotBoxKey: PublicKey, programId: PublicKey, pollId: number) => {
+    const ix = createTallyRoundInstruction(pollPublicKey, ballotBoxKey, programId, pollId);
+    return await sendInstruction(connection, from, ix);
+}
+
+//voter takes back their escrowed tokens once voting is over
+export const createWithdrawInstruction = (voter: PublicKey, pollAccount: PublicKey, escrowAccount: PublicKey, tokenAccount: PublicKey, creator: PublicKey, pid: PublicKey, pollId: number) => {
+    return new TransactionInstruction(
+        {
+            keys: [
+                {
+                    pubkey: pollAccount,
+                    isSigner: false,
+                    isWritable: false
+                },
+                {
+                    pubkey: escrowAccount,
+                    isSigner: false,
+                    isWritable: true
+                },
+                {
+                    pubkey: tokenAccount,
+                    isSigner: false,
+                    isWritable: true
+                },
+                {
+                    pubkey: voter,
+                    isSigner: true,
+                    isWritable: true
+                },
+                {
+                    pubkey: creator,
+                    isSigner: false,
+                    isWritable: false
+                },
+                {
+                    pubkey: TOKEN_PROGRAM_ID,
+                    isSigner: false,
+                    isWritable: false
+                }
+            ],
+            data: Buffer.concat([Buffer.from([9]), new BN(pollId).toArrayLike(Buffer, "le", 8)]),
+            programId: pid
+        }
+    );
+}
+
+export const withdrawTransaction = async (connection: Connection, from: PhantomProvider, pollPublicKey: PublicKey, escrowKey: PublicKey, tokenAccount: PublicKey, creator: PublicKey, programId: PublicKey, pollId: number) => {
+    const ix = createWithdrawInstruction(from!.publicKey!, pollPublicKey, escrowKey, tokenAccount, creator, programId, pollId);
+    return await sendInstruction(connection, from, ix);
+}
+
+//delegator assigns their vote on a poll, or on every poll when poll is the default key
+//borsh encoding of a proposal instruction, u32 lengths before the account metas and data
+const proposalInstructionData = (instruction: ProposalInstruction) => {
+    const u32 = (value: number) => new BN(value).toArrayLike(Buffer, "le", 4);
+    return Buffer.concat([instruction.programId.toBuffer(), u32(instruction.keys.length),
+    ...instruction.keys.map(key => Buffer.concat([key.pubkey.toBuffer(), Buffer.from([key.isSigner ? 1 : 0, key.isWritable ? 1 : 0])])),
+    u32(instruction.data.length), instruction.data]);
+}
+
+//creator attaches an instruction to an executable poll before voting starts, the poll has to meet
+//the creator's governance config
+export const createAddProposalInstructionInstruction = (creator: PublicKey, pollAccount: PublicKey, proposalAccount: PublicKey, governanceConfigAccount: PublicKey, pid: PublicKey, pollId: number, instruction: ProposalInstruction) => {
+    return new TransactionInstruction(
+        {
+            keys: [
+                {
+                    pubkey: pollAccount,
+                    isSigner: false,
+                    isWritable: true
+                },
+                {
+                    pubkey: proposalAccount,
+                    isSigner: false,
+                    isWritable: true
+                },
+                {
+                    pubkey: creator,
+                    isSigner: true,
+                    isWritable: true
+                },
+                {
+                    pubkey: SystemProgram.programId,
+                    isSigner: false,
+                    isWritable: false
+                },
+                {
+                    pubkey: governanceConfigAccount,
+                    isSigner: false,
+                    isWritable: false
+                }
+            ],
+            data: Buffer.concat([Buffer.from([14]), new BN(pollId).toArrayLike(Buffer, "le", 8), proposalInstructionData(instruction)]),
+            programId: pid
+        }
+    );
+}
+
+export const addProposalInstructionTransaction = async (connection: Connection, from: PhantomProvider, pollPublicKey: PublicKey, proposalKey: PublicKey, governanceConfigKey: PublicKey, programId: PublicKey, pollId: number, instruction: ProposalInstruction) => {
+    const ix = createAddProposalInstructionInstruction(from!.publicKey!, pollPublicKey, proposalKey, governanceConfigKey, programId, pollId, instruction);
+    return await sendInstruction(connection, from, ix);
+}
+
+//anyone runs the instructions of a queued proposal once its timelock is over, the governance pda signs them
+export const createExecuteProposalInstruction = (pollAccount: PublicKey, proposalAccount: PublicKey, governanceAccount: PublicKey, pid: PublicKey, pollId: number, instructions: ProposalInstruction[]) => {
+    //programs and accounts of the instructions, the governance pda signs inside the program
+    const keys: AccountMeta[] = [];
+    for (const instruction of instructions)
+        keys.push({ pubkey: instruction.programId, isSigner: false, isWritable: false },
+            ...instruction.keys.map(key => ({ ...key, isSigner: key.isSigner && !key.pubkey.equals(governanceAccount) })));
+    return new TransactionInstruction(
+        {
+            keys: [
+                {
+                    pubkey: pollAccount,
+                    isSigner: false,
+                    isWritable: true
+                },
+                {
+                    pubkey: proposalAccount,
+                    isSigner: false,
+                    isWritable: false
+                },
+                {
+                    pubkey: governanceAccount,
+                    isSigner: false,
+                    isWritable: false
+                },
+                ...keys
+            ],
+            data: Buffer.concat([Buffer.from([15]), new BN(pollId).toArrayLike(Buffer, "le", 8)]),
+            programId: pid
+        }
+    );
+}
+
+export const executeProposalTransaction = async (connection: Connection, from: PhantomProvider, pollPublicKey: PublicKey, proposalKey: PublicKey, governanceKey: PublicKey, programId: PublicKey, pollId: number, instructions: ProposalInstruction[]) => {
+    const ix = createExecuteProposalInstruction(pollPublicKey, proposalKey, governanceKey, programId, pollId, instructions);
+    return await sendInstruction(connection, from, ix);
+}
+
+//creator or council aborts a queued proposal while it is timelocked
+export const createCancelProposalInstruction = (authority: PublicKey, pollAccount: PublicKey, pid: PublicKey, pollId: number) => {
+    return new TransactionInstruction(
+        {
+            keys: [
+                {
+                    pubkey: pollAccount,
+                    isSigner: false,
+                    isWritable: true
+                },
+                {
+                    pubkey: authority,
+                    isSigner: true,
+                    isWritable: false
+                }
+            ],
+            data: Buffer.concat([Buffer.from([16]), new BN(pollId).toArrayLike(Buffer, "le", 8)]),
+            programId: pid
+        }
+    );
+}
+
+export const cancelProposalTransaction = async (connection: Connection, from: PhantomProvider, pollPublicKey: PublicKey, programId: PublicKey, pollId: number) => {
+    const ix = createCancelProposalInstruction(from!.publicKey!, pollPublicKey, programId, pollId);
+    return await sendInstruction(connection, from, ix);
+}
+
+//anyone deposits lamports (mint is default) or tokens from their token account into a creator's treasury
+export const createFundTreasuryInstruction = (funder: PublicKey, treasuryAccount: PublicKey, pid: PublicKey, creator: PublicKey, mint: PublicKey, amount: BN, token?: { tokenAccount: PublicKey, treasuryTokenAccount: PublicKey }) => {
+    const tokenKeys = token ? [
+        { pubkey: token.tokenAccount, isSigner: false, isWritable: true },
+        { pubkey: mint, isSigner: false, isWritable: false },
+        { pubkey: token.treasuryTokenAccount, isSigner: false, isWritable: true },
+        { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false }] : [];
+    return new TransactionInstruction(
+        {
+            keys: [
+                {
+                    pubkey: treasuryAccount,
+                    isSigner: false,
+                    isWritable: true
+                },
+                {
+                    pubkey: funder,
+                    isSigner: true,
+                    isWritable: true
+                },
+                {
+                    pubkey: SystemProgram.programId,
+                    isSigner: false,
+                    isWritable: false
+                },
+                ...tokenKeys
+            ],
+            data: Buffer.concat([Buffer.from([17]), creator.toBuffer(), mint.toBuffer(), amount.toArrayLike(Buffer, "le", 8)]),
+            programId: pid
+        }
+    );
+}
+
+export const fundTreasuryTransaction = async (connection: Connection, from: PhantomProvider, treasuryKey: PublicKey, programId: PublicKey, creator: PublicKey, mint: PublicKey, amount: BN, token?: { tokenAccount: PublicKey, treasuryTokenAccount: PublicKey }) => {
+    const ix = createFundTreasuryInstruction(from!.publicKey!, treasuryKey, programId, creator, mint, amount, token);
+    return await sendInstruction(connection, from, ix);
+}
+
+//anyone pays out a passed spend poll once its timelock is over, recipientAccount is the recipient's
+//token account on token spends, which also need the treasury's token account
+export const createExecuteSpendInstruction = (pollAccount: PublicKey, treasuryAccount: PublicKey, recipientAccount: PublicKey, pid: PublicKey, pollId: number, treasuryTokenAccount?: PublicKey) => {
+    const tokenKeys = treasuryTokenAccount ? [
+        { pubkey: treasuryTokenAccount, isSigner: false, isWritable: true },
+        { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false }] : [];
+    return new TransactionInstruction(
+        {
+            keys: [
+                {
+                    pubkey: pollAccount,
+                    isSigner: false,
+                    isWritable: true
+                },
+                {
+                    pubkey: treasuryAccount,
+                    isSigner: false,
+                    isWritable: true
+                },
+                {
+                    pubkey: recipientAccount,
+                    isSigner: false,
+                    isWritable: true
+                },
+                {
+                    pubkey: SystemProgram.programId,
+                    isSigner: false,
+                    isWritable: false
+                },
+                ...tokenKeys
+            ],
+            data: Buffer.concat([Buffer.from([18]), new BN(pollId).toArrayLike(Buffer, "le", 8)]),
+            programId: pid
+        }
+    );
+}
+
+export const executeSpendTransaction = async (connection: Connection, from: PhantomProvider, pollPublicKey: PublicKey, treasuryKey: PublicKey, recipientKey: PublicKey, programId: PublicKey, pollId: number, treasuryTokenKey?: PublicKey) => {
+    const ix = createExecuteSpendInstruction(pollPublicKey, treasuryKey, recipientKey, programId, pollId, treasuryTokenKey);
+    return await sendInstruction(connection, from, ix);
+}
+
+//veto keys block a passed poll before it is executed, every veto key in vetoKeys signs
+export const createVetoPollInstruction = (vetoKeys: PublicKey[], pollAccount: PublicKey, pid: PublicKey, pollId: number) => {
+    return new TransactionInstruction(
+        {
+            keys: [
+                {
+                    pubkey: pollAccount,
+                    isSigner: false,
+                    isWritable: true
+                },
+                ...vetoKeys.map(key => ({ pubkey: key, isSigner: true, isWritable: false }))
+            ],
+            data: Buffer.concat([Buffer.from([19]), new BN(pollId).toArrayLike(Buffer, "le", 8)]),
+            programId: pid
+        }
+    );
+}
+
+//a single wallet vetoes, polls needing more veto signatures are vetoed with a transaction all their signers sign
+export const vetoPollTransaction = async (connection: Connection, from: PhantomProvider, pollPublicKey: PublicKey, programId: PublicKey, pollId: number) => {
+    const ix = createVetoPollInstruction([from!.publicKey!], pollPublicKey, programId, pollId);
+    return await sendInstruction(connection, from, ix);
+}
+
+export const createDelegateInstruction = (delegator: PublicKey, delegationAccount: PublicKey, delegate: PublicKey, pid: PublicKey, poll: PublicKey = PublicKey.default) => {
+    return new TransactionInstruction(
+        {
+            keys: [
+                {
+                    pubkey: delegationAccount,
+                    isSigner: false,
+                    isWritable: true
+                },
+                {
+                    pubkey: delegator,
+                    isSigner: true,
+                    isWritable: true
+                },
+                {
+                    pubkey: delegate,
+                    isSigner: false,
+                    isWritable: false
+                },
+                {
+                    pubkey: SystemProgram.programId,
+                    isSigner: false,
+                    isWritable: false
+                }
+            ],
+            data: Buffer.concat([Buffer.from([11]), poll.toBuffer()]),
+            programId: pid
+        }
+    );
+}
+
+export const delegateTransaction = async (connection: Connection, from: PhantomProvider, delegationKey: PublicKey, delegate: PublicKey, programId: PublicKey, poll?: PublicKey) => {
+    const ix = createDelegateInstruction(from!.publicKey!, delegationKey, delegate, programId, poll);
+    return await sendInstruction(connection, from, ix);
+}
+
+export const createUndelegateInstruction = (delegator: PublicKey, delegationAccount: PublicKey, pid: PublicKey, poll: PublicKey = PublicKey.default) => {
+    return new TransactionInstruction(
+        {
+            keys: [
+                {
+                    pubkey: delegationAccount,
+                    isSigner: false,
+                    isWritable: true
+                },
+                {
+                    pubkey: delegator,
+                    isSigner: true,
+                    isWritable: true
+                }
+            ],
+            data: Buffer.concat([Buffer.from([12]), poll.toBuffer()]),
+            programId: pid
+        }
+    );
+}
+
+export const undelegateTransaction = async (connection: Connection, from: PhantomProvider, delegationKey: PublicKey, programId: PublicKey, poll?: PublicKey) => {
+    const ix = createUndelegateInstruction(from!.publicKey!, delegationKey, programId, poll);
+    return await sendInstruction(connection, from, ix);
+}
+
+//creator fixes the minimum quorum, threshold (basis points) and voting period (seconds) of their
+//executable polls, once
+export const createSetGovernanceConfigInstruction = (creator: PublicKey, governanceConfigAccount: PublicKey, pid: PublicKey, minQuorum: BN, minThreshold: number, minVotingPeriod: number) => {
+    return new TransactionInstruction(
+        {
+            keys: [
+                {
+                    pubkey: governanceConfigAccount,
+                    isSigner: false,
+                    isWritable: true
+                },
+                {
+                    pubkey: creator,
+                    isSigner: true,
+                    isWritable: true
+                },
+                {
+                    pubkey: SystemProgram.programId,
+                    isSigner: false,
+                    isWritable: false
+                }
+            ],
+            data: Buffer.concat([Buffer.from([20]), minQuorum.toArrayLike(Buffer, "le", 8), new BN(minThreshold).toArrayLike(Buffer, "le", 2), new BN(minVotingPeriod).toArrayLike(Buffer, "le", 8)]),
+            programId: pid
+        }
+    );
+}
+
+export const setGovernanceConfigTransaction = async (connection: Connection, from: PhantomProvider, governanceConfigKey: PublicKey, programId: PublicKey, minQuorum: BN, minThreshold: number, minVotingPeriod: number) => {
+    const ix = createSetGovernanceConfigInstruction(from!.publicKey!, governanceConfigKey, programId, minQuorum, minThreshold, minVotingPeriod);
+    return await sendInstruction(connection, from, ix);
+}
@@ -1,44 +1,61 @@
 import { Connection, PublicKey } from "@solana/web3.js";
 import { useEffect, useState } from "react";
 import { getPda, getPdaAccount, getPollCountAccount, setAccountUpdateCallback } from "../solana/accounts";
-import { getPollCountSeedArray, PollCount } from "../solana/solutil";
+import { getPollCountSeedArray, getProvider, PollCount } from "../solana/solutil";
 import Create from "./vote/Create";
 import List from "./vote/List";
 
 const Vote = ({ connection, programId }: { connection: Connection, programId: PublicKey }) => {
     const [createPoll, setCreatePoll] = useState<Boolean>(false);
     const [pollCount, setPollCount] = useState<PollCount | null>();
+    const [walletPollCount, setWalletPollCount] = useState<PollCount | null>();
+    const [creator, setCreator] = useState<PublicKey>(getProvider()!.publicKey!);
 
-    const loadPollCountAccount = async () => {
-        const pollSeeds = getPollCountSeedArray();
+    const loadPollCountAccount = async (creatorKey: PublicKey) => {
+        const pollSeeds = getPollCountSeedArray(creatorKey);
         const pda = await getPdaAccount(connection, programId, pollSeeds);
-        const acc = getPollCountAccount(pda);
-        setPollCount(acc);
+        return getPollCountAccount(pda);
     }
 
     const accountChangeCallback = async () => {
-        await loadPollCountAccount();
+        setWalletPollCount(await loadPollCountAccount(getProvider()!.publicKey!));
     }
 
     const initCallback = async () => {
-        setAccountUpdateCallback((await getPda(programId, getPollCountSeedArray()))[0], accountChangeCallback, connection);
+        setAccountUpdateCallback((await getPda(programId, getPollCountSeedArray(getProvider()!.publicKey!)))[0], accountChangeCallback, connection);
+    }
+
+    const changeCreator = (value: string) => {
+        try {
+            setCreator(new PublicKey(value));
+        } catch (err) {
+            //not a valid pubkey yet
+        }
     }
 
     useEffect(() => {
-        loadPollCountAccount();
+        accountChangeCallback();
         initCallback();
 
     }, [])
 
+    useEffect(() => {
+        loadPollCountAccount(creator).then(setPollCount);
+    }, [creator, walletPollCount])
+
     return (
         <>
             <div>
                 <div className="padding10">
                     <button onClick={() => { setCreatePoll(true) }}>Create New Poll</button>
-                    {createPoll && <Create connection={connection} programId={programId} newPollId={(pollCount && pollCount.count + 1) || 1} setCreatePoll={setCreatePoll} />}
+                    {createPoll && <Create connection={connection} programId={programId} newPollId={(walletPollCount && walletPollCount.count + 1) || 1} setCreatePoll={setCreatePoll} />}
+                </div>
+                <div className="padding10">
+                    <label>Creator </label>
+                    <input type="text" className="form-control" defaultValue={creator.toBase58()} onChange={(e) => { changeCreator(e.target.value) }} />
                 </div>
                 <div className="padding10">
-                    <List connection={connection} programId={programId} pollId={(pollCount && pollCount.count) || 0} />
+                    <List key={creator.toBase58()} connection={connection} programId={programId} creator={creator} pollId={(pollCount && pollCount.count) || 0} />
                 </div>
 
             </div>
@@ -46,4 +63,4 @@ const Vote = ({ connection, programId }: { connection: Connection, programId: Pu
     );
 }
 
-export default Vote;
\ No newline at end of file
+export default Vote;
@@ -1,28 +1,91 @@
 import { Connection, PublicKey } from "@solana/web3.js";
-import { Buffer } from 'buffer';
-
 import { useEffect } from "react";
 import { useFieldArray, useForm } from "react-hook-form";
+import { Buffer } from 'buffer';
+import BN from "bn.js";
 import { getPda, getPdaAccount, getPollCountAccount } from "../../solana/accounts";
-import { getPollCountSeedArray, getProvider, poll_seed } from "../../solana/solutil";
-import { createProposal } from "../../solana/transaction";
+import { getAllowlistRoot, getBallotBoxSeedArray, getGovernanceConfigSeedArray, getPollCountSeedArray, getPollSeedArray, getProvider, MAX_OPTIONS, MIN_OPTIONS, VETO_KEYS, VoteMode } from "../../solana/solutil";
+import { createProposal, setGovernanceConfigTransaction } from "../../solana/transaction";
 
 const Create = ({ connection, programId, newPollId, setCreatePoll }: { connection: Connection, programId: PublicKey, newPollId: number, setCreatePoll: any }) => {
 
     type VoteForm = {
         title: string | null,
-        options: string[] | null
+        options: string[] | null,
+        start: string | null,
+        end: string | null,
+        revealEnd: string | null,
+        allowRevote: boolean,
+        allowAbstain: boolean,
+        allowNota: boolean,
+        voteMode: string,
+        maxSelections: string | null,
+        voteMint: string | null,
+        lockTokens: boolean,
+        credits: string | null,
+        allowlist: string | null,
+        nftCollection: string | null,
+        quorum: string | null,
+        threshold: string | null,
+        passOption: string | null,
+        timelock: string | null,
+        council: string | null,
+        spendRecipient: string | null,
+        spendMint: string | null,
+        spendAmount: string | null,
+        vetoKeys: string | null,
+        vetoThreshold: string | null,
+        minQuorum: string | null,
+        minThreshold: string | null,
+        minVotingPeriod: string | null
     }
     const { register, control, handleSubmit, watch } = useForm();
     const { fields, append, remove } = useFieldArray({ name: "options", control });
     const onSubmit = async (data: VoteForm) => {
         const title = data.title;
         const options = data.options.map((val: any) => val.value);
-        const pollSeeds = getPollCountSeedArray();
+        //0 leaves the voting window open on that side
+        const startTs = data.start ? Math.floor(new Date(data.start).getTime() / 1000) : 0;
+        const endTs = data.end ? Math.floor(new Date(data.end).getTime() / 1000) : 0;
+        //ballots are committed until voting ends and revealed until then, left empty for plain ballots
+        const revealEndTs = data.revealEnd ? Math.floor(new Date(data.revealEnd).getTime() / 1000) : 0;
+        const creator = getProvider()!.publicKey!;
+        const pollSeeds = getPollCountSeedArray(creator);
         const [countPda, bump] = await getPda(programId, pollSeeds);
         const countAcc = await getPollCountAccount(await getPdaAccount(connection, programId, pollSeeds));
-        const [pollPda, bump2] = await getPda(programId, [Buffer.from(poll_seed), new Uint8Array([countAcc.count + 1])]);
-        await createProposal(connection, getProvider(), countPda, pollPda, programId, title, options);
+        const [pollPda, bump2] = await getPda(programId, getPollSeedArray(creator, ((countAcc && countAcc.count) || 0) + 1));
+        const voteMode = parseInt(data.voteMode || "0");
+        //0 lets approval voters select every option
+        const maxSelections = voteMode === VoteMode.Approval && data.maxSelections ? parseInt(data.maxSelections) : 0;
+        const ballotBox = voteMode === VoteMode.Ranked ? (await getPda(programId, getBallotBoxSeedArray(creator, ((countAcc && countAcc.count) || 0) + 1, bump2)))[0] : undefined;
+        const credits = voteMode === VoteMode.Quadratic && data.credits ? parseInt(data.credits) : 0;
+        //votes are weighted by the balance of this token, left empty for one vote per voter
+        const voteMint = data.voteMint ? new PublicKey(data.voteMint) : PublicKey.default;
+        //only the listed voters (one pubkey per line) can vote, left empty for everyone
+        const allowlist = (data.allowlist || "").split(/\s+/).filter(key => key.length > 0).map(key => new PublicKey(key));
+        const allowlistRoot = allowlist.length > 0 ? await getAllowlistRoot(allowlist) : Buffer.alloc(32);
+        //only holders of a verified nft of this collection can vote, left empty for everyone
+        const nftCollection = data.nftCollection ? new PublicKey(data.nftCollection) : PublicKey.default;
+        //the proposal passes when quorum votes are cast and the pass option (the winner if 0)
+        //gets more than threshold percent of them
+        const quorum = data.quorum ? parseInt(data.quorum) : 0;
+        const threshold = data.threshold ? Math.round(parseFloat(data.threshold) * 100) : 0;
+        const passOption = data.passOption ? parseInt(data.passOption) : 0;
+        //a passed proposal waits timelock hours before it runs, the creator or council can cancel it meanwhile
+        const timelock = data.timelock ? Math.round(parseFloat(data.timelock) * 3600) : 0;
+        const council = data.council ? new PublicKey(data.council) : PublicKey.default;
+        //a spend poll pays the amount (lamports, or the mint's smallest unit) out of the creator's treasury once it passed
+        const spendRecipient = data.spendRecipient ? new PublicKey(data.spendRecipient) : PublicKey.default;
+        const spendMint = data.spendMint ? new PublicKey(data.spendMint) : PublicKey.default;
+        const spendAmount = data.spendRecipient && data.spendAmount ? new BN(data.spendAmount) : new BN(0);
+        //vetoThreshold of the veto keys (one pubkey per line) can veto the poll once it passed, left empty for no veto
+        const vetoKeys = (data.vetoKeys || "").split(/\s+/).filter(key => key.length > 0).map(key => new PublicKey(key));
+        const vetoThreshold = vetoKeys.length > 0 ? (data.vetoThreshold ? parseInt(data.vetoThreshold) : 1) : 0;
+        //the creator's governance minimums are set once, before the first executable or spend poll
+        const [governanceConfig] = await getPda(programId, getGovernanceConfigSeedArray(creator));
+        if (data.minQuorum && !(await connection.getAccountInfo(governanceConfig)))
+            await setGovernanceConfigTransaction(connection, getProvider(), governanceConfig, programId, new BN(data.minQuorum), Math.round(parseFloat(data.minThreshold || "0") * 100), Math.round(parseFloat(data.minVotingPeriod || "0") * 3600));
+        await createProposal(connection, getProvider(), countPda, pollPda, programId, title, options, startTs, endTs, data.allowRevote, voteMode, maxSelections, voteMint, !!data.voteMint && data.lockTokens, credits, allowlistRoot, nftCollection, revealEndTs, quorum, threshold, passOption, timelock, council, spendRecipient, spendMint, spendAmount, vetoThreshold, vetoKeys, voteMode !== VoteMode.Ranked && data.allowAbstain, voteMode !== VoteMode.Ranked && data.allowNota, ballotBox, spendAmount.isZero() ? undefined : governanceConfig);
         setCreatePoll(false);
     }
 
@@ -47,7 +110,7 @@ const Create = ({ connection, programId, newPollId, setCreatePoll }: { connectio
                 <div className="item">Proposal #: {(newPollId)}<div className="item">
                     <label>No. of Options</label>
                     <select name="optionCount" {...register("optionCount" as const)}>
-                        {[2, 3, 4].map(i => <option key={i} value={i}>{i}</option>)}
+                        {Array.from(Array(MAX_OPTIONS - MIN_OPTIONS + 1).keys()).map(i => i + MIN_OPTIONS).map(i => <option key={i} value={i}>{i}</option>)}
                     </select>
                 </div></div>
             </div>
@@ -57,6 +120,142 @@ const Create = ({ connection, programId, newPollId, setCreatePoll }: { connectio
                 </div>
             </div>
 
+            <div className="form-row">
+                <div className="field">
+                    <label>Voting starts</label>
+                    <input name="start" {...register("start" as const)} type="datetime-local" className="form-control" />
+                </div>
+                <div className="field">
+                    <label>Voting ends</label>
+                    <input name="end" {...register("end" as const)} type="datetime-local" className="form-control" />
+                </div>
+                <div className="field">
+                    <label>Secret ballots revealed until (single choice, optional)</label>
+                    <input name="revealEnd" {...register("revealEnd" as const)} type="datetime-local" className="form-control" />
+                </div>
+            </div>
+
+            <div className="form-row">
+                <div className="field">
+                    <label>Allow changing votes</label>
+                    <input name="allowRevote" {...register("allowRevote" as const)} type="checkbox" />
+                </div>
+                <div className="field">
+                    <label>Offer abstain (not on ranked polls)</label>
+                    <input name="allowAbstain" {...register("allowAbstain" as const)} type="checkbox" />
+                </div>
+                <div className="field">
+                    <label>Offer none of the above (not on ranked polls)</label>
+                    <input name="allowNota" {...register("allowNota" as const)} type="checkbox" />
+                </div>
+            </div>
+
+            <div className="form-row">
+                <div className="field">
+                    <label>Voting</label>
+                    <select name="voteMode" {...register("voteMode" as const)}>
+                        <option value={VoteMode.Single}>Single choice</option>
+                        <option value={VoteMode.Approval}>Approval (select several options)</option>
+                        <option value={VoteMode.Ranked}>Ranked choice (instant runoff)</option>
+                        <option value={VoteMode.Quadratic}>Quadratic (spend voice credits)</option>
+                    </select>
+                </div>
+                <div className="field">
+                    <label>Max selections (0 for no limit)</label>
+                    <input name="maxSelections" {...register("maxSelections" as const)} type="number" min={0} max={MAX_OPTIONS} className="form-control" />
+                </div>
+                <div className="field">
+                    <label>Voice credits per voter (quadratic)</label>
+                    <input name="credits" {...register("credits" as const)} type="number" min={1} className="form-control" />
+                </div>
+            </div>
+
+            <div className="form-row">
+                <div className="field">
+                    <input name="voteMint" {...register("voteMint" as const)} type="text" className="form-control" placeholder="Token mint to weight votes by (optional)" />
+                </div>
+                <div className="field">
+                    <label>Lock voted tokens until voting is over</label>
+                    <input name="lockTokens" {...register("lockTokens" as const)} type="checkbox" />
+                </div>
+            </div>
+
+            <div className="form-row">
+                <div className="field">
+                    <textarea name="allowlist" {...register("allowlist" as const)} className="form-control" placeholder="Voters allowed to vote, one pubkey per line (optional)" />
+                </div>
+                <div className="field">
+                    <input name="nftCollection" {...register("nftCollection" as const)} type="text" className="form-control" placeholder="Nft collection voters have to hold (optional)" />
+                </div>
+            </div>
+
+            <div className="form-row">
+                <div className="field">
+                    <label>Quorum, votes to be cast (optional)</label>
+                    <input name="quorum" {...register("quorum" as const)} type="number" min={0} className="form-control" />
+                </div>
+                <div className="field">
+                    <label>Passes with more than % of votes (50 majority, 66.66 two thirds)</label>
+                    <input name="threshold" {...register("threshold" as const)} type="number" min={0} max={100} step={0.01} className="form-control" />
+                </div>
+                <div className="field">
+                    <label>Option that has to pass (0 for the winner)</label>
+                    <input name="passOption" {...register("passOption" as const)} type="number" min={0} max={MAX_OPTIONS} className="form-control" />
+                </div>
+            </div>
+
+            <div className="form-row">
+                <div className="field">
+                    <label>Timelock before a passed proposal runs, in hours (optional)</label>
+                    <input name="timelock" {...register("timelock" as const)} type="number" min={0} step={0.01} className="form-control" />
+                </div>
+                <div className="field">
+                    <label>Council that can cancel a queued proposal (optional)</label>
+                    <input name="council" {...register("council" as const)} type="text" className="form-control" />
+                </div>
+            </div>
+
+            <div className="form-row">
+                <div className="field">
+                    <label>Spend recipient, pays out of the treasury once passed (optional)</label>
+                    <input name="spendRecipient" {...register("spendRecipient" as const)} type="text" className="form-control" />
+                </div>
+                <div className="field">
+                    <label>Spend mint (empty for SOL)</label>
+                    <input name="spendMint" {...register("spendMint" as const)} type="text" className="form-control" />
+                </div>
+                <div className="field">
+                    <label>Spend amount (lamports or token units)</label>
+                    <input name="spendAmount" {...register("spendAmount" as const)} type="number" min={0} className="form-control" />
+                </div>
+            </div>
+
+            <div className="form-row">
+                <div className="field">
+                    <label>Veto keys, one pubkey per line, up to {VETO_KEYS} (optional)</label>
+                    <textarea name="vetoKeys" {...register("vetoKeys" as const)} className="form-control" />
+                </div>
+                <div className="field">
+                    <label>Veto keys needed to veto (1 if empty)</label>
+                    <input name="vetoThreshold" {...register("vetoThreshold" as const)} type="number" min={1} max={VETO_KEYS} className="form-control" />
+                </div>
+            </div>
+
+            <div className="form-row">
+                <div className="field">
+                    <label>Governance minimum quorum, set once for all your executable and spend polls</label>
+                    <input name="minQuorum" {...register("minQuorum" as const)} type="number" min={1} className="form-control" />
+                </div>
+                <div className="field">
+                    <label>Governance minimum % to pass</label>
+                    <input name="minThreshold" {...register("minThreshold" as const)} type="number" min={0} max={100} step={0.01} className="form-control" />
+                </div>
+                <div className="field">
+                    <label>Governance minimum voting period, in hours</label>
+                    <input name="minVotingPeriod" {...register("minVotingPeriod" as const)} type="number" min={0} step={0.01} className="form-control" />
+                </div>
+            </div>
+
             {fields.map((field, index) => (
                 <div className="field" key={field.id}>
                     <input name="options" {...register(`options.${index}.value` as const)} type="text" className="form-control" placeholder={"Option " + (index + 1)} />
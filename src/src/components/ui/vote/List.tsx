@@ -2,7 +2,7 @@ import { Connection, PublicKey } from "@solana/web3.js";
 import { useState } from "react";
 import View from "./View";
 
-const List = ({ connection, programId, pollId }: { connection: Connection, programId: PublicKey, pollId: number }) => {
+const List = ({ connection, programId, creator, pollId }: { connection: Connection, programId: PublicKey, creator: PublicKey, pollId: number }) => {
     const [currentProposal, setCurrentProposal] = useState<number | null>();
 
     return <>
@@ -14,7 +14,7 @@ const List = ({ connection, programId, pollId }: { connection: Connection, progr
                     Array.from(Array(pollId).keys()).map((val) => {
                         return <li key={val}>
                             <button onClick={() => { if (currentProposal === val + 1) setCurrentProposal(null); else setCurrentProposal(val + 1); }}>{(currentProposal !== null && currentProposal === val + 1 && "Hide Proposal") || "View Proposal"} : {val + 1}</button>
-                            {currentProposal !== null && currentProposal === val + 1 && <View connection={connection} programId={programId} pollId={val + 1} />}
+                            {currentProposal !== null && currentProposal === val + 1 && <View connection={connection} programId={programId} creator={creator} pollId={val + 1} />}
                         </li>
                     })
                 }
@@ -1,28 +1,138 @@
-import { Connection, PublicKey } from "@solana/web3.js";
+import { AccountMeta, Connection, PublicKey } from "@solana/web3.js";
 import { useEffect, useState } from "react";
-import { getPda, getPdaAccount, getPollAccount, getVoterPollAccount, setAccountUpdateCallback } from "../../solana/accounts";
-import { getProvider, Poll, PollVoter, poll_seed } from "../../solana/solutil";
-import { Buffer } from 'buffer';
+import { getPda, getPdaAccount, getPollAccount, getProposalAccount, getVoterPollAccount, getVoterTokenAccount, setAccountUpdateCallback } from "../../solana/accounts";
+import BN from "bn.js";
+import { Buffer } from "buffer";
+import { ABSTAIN_OPTION_ID, getAllowlistProof, getBallotBoxSeedArray, getDelegationSeedArray, getEscrowSeedArray, getGovernanceConfigSeedArray, getGovernanceSeedArray, getTreasurySeedArray, getTreasuryTokenSeedArray, getNftMetadataSeedArray, getPollSeedArray, getProposalSeedArray, getProvider, getVoteCommitment, NOTA_OPTION_ID, Poll, PollOutcome, PollStatus, PollVoter, Proposal, ProposalState, TOKEN_METADATA_PROGRAM_ID, VoteMode } from "../../solana/solutil";
 
-import { voteTransaction } from "../../solana/transaction";
+import { addProposalInstructionTransaction, approvalVoteTransaction, cancelProposalTransaction, closePollAccountTransaction, closePollTransaction, closeVoterAccountTransaction, commitVoteTransaction, delegateTransaction, executeProposalTransaction, executeSpendTransaction, fundTreasuryTransaction, getVoteDelegationKeys, getVoteNftKeys, getVoteTokenKeys, quadraticVoteTransaction, rankedVoteTransaction, retractVoteTransaction, revealVoteTransaction, tallyRoundTransaction, undelegateTransaction, vetoPollTransaction, voteTransaction, withdrawTransaction } from "../../solana/transaction";
 
-const View = ({ connection, programId, pollId }: { connection: Connection, programId: PublicKey, pollId: number }) => {
+const View = ({ connection, programId, creator, pollId }: { connection: Connection, programId: PublicKey, creator: PublicKey, pollId: number }) => {
 
     const [poll, setPoll] = useState<Poll | null | undefined>();
     const [userVote, setUserVote] = useState<PollVoter | null | undefined>();
+    //options ticked for an approval ballot, bit 0 is option 1
+    const [selections, setSelections] = useState<BN>(new BN(0));
+    //option ids of a ranked ballot, by preference
+    const [ranking, setRanking] = useState<number[]>([]);
+    //votes for each option of a quadratic ballot
+    const [allocation, setAllocation] = useState<number[]>([]);
+    //members of an allowlisted poll, one pubkey per line, to prove the voter is one of them
+    const [allowlist, setAllowlist] = useState<string>("");
+    //mint of the nft voting on an nft gated poll
+    const [nftMint, setNftMint] = useState<string>("");
+    //wallet to delegate the vote on this poll to
+    const [delegate, setDelegate] = useState<string>("");
+    //wallets that delegated to the voter, one pubkey per line, voted for along with the voter
+    const [delegators, setDelegators] = useState<string>("");
+    //instructions of an executable poll
+    const [proposal, setProposal] = useState<Proposal | null>(null);
+    //instruction the creator attaches before voting starts: program id,
+    //one account per line ("pubkey", followed by "s" for signers and "w" for writable accounts), hex data
+    const [instructionProgram, setInstructionProgram] = useState<string>("");
+    const [instructionAccounts, setInstructionAccounts] = useState<string>("");
+    const [instructionData, setInstructionData] = useState<string>("");
+    //lamports, or token units of the spend mint, deposited into the creator's treasury
+    const [fundAmount, setFundAmount] = useState<string>("");
+
+    const isNftGated = () => !!poll && !poll.nftCollection.equals(PublicKey.default);
+
+    const getNftMint = () => {
+        try {
+            return isNftGated() && nftMint ? new PublicKey(nftMint) : undefined;
+        } catch {
+            return undefined;
+        }
+    }
+
+    //voter pdas are seeded by the voter, on nft gated polls by the nft mint
+    const getVoterSeed = () => (getNftMint() || getProvider()!.publicKey!).toBuffer();
+
+    //voter's token account holding the nft
+    const getNftAccount = async () => {
+        const mint = getNftMint();
+        return mint && await getVoterTokenAccount(connection, getProvider()!.publicKey!, mint);
+    }
 
     const loadPoll = async () => {
-        const seeds: Uint8Array[] = [Buffer.from(poll_seed), new Uint8Array([pollId])];
+        const seeds: Uint8Array[] = getPollSeedArray(creator, pollId);
         const pda = await getPdaAccount(connection, programId, seeds);
         const acc = getPollAccount(pda);
         setPoll(acc);
     };
 
+    const getProposalKey = async () => {
+        const [poll_pda, bump] = await getPda(programId, getPollSeedArray(creator, pollId));
+        return (await getPda(programId, getProposalSeedArray(creator, pollId, bump)))[0];
+    }
+
+    const loadProposal = async () => {
+        setProposal(getProposalAccount(await connection.getAccountInfo(await getProposalKey())));
+    }
+
+    const addProposalInstruction = async () => {
+        const wallet = getProvider();
+        const [poll_pda, bump] = await getPda(programId, getPollSeedArray(creator, pollId));
+        const keys = instructionAccounts.split("\n").map(line => line.trim().split(/\s+/)).filter(parts => parts[0].length > 0).map(parts => ({
+            pubkey: new PublicKey(parts[0]),
+            isSigner: (parts[1] || "").includes("s"),
+            isWritable: (parts[1] || "").includes("w")
+        }));
+        const [governanceConfig] = await getPda(programId, getGovernanceConfigSeedArray(creator));
+        await addProposalInstructionTransaction(connection, wallet!, poll_pda, await getProposalKey(), governanceConfig, programId, pollId, { programId: new PublicKey(instructionProgram), keys, data: Buffer.from(instructionData, "hex") });
+        await loadProposal();
+    }
+
+    const executeProposal = async () => {
+        const wallet = getProvider();
+        const [poll_pda, bump] = await getPda(programId, getPollSeedArray(creator, pollId));
+        const [governance] = await getPda(programId, getGovernanceSeedArray(creator));
+        await executeProposalTransaction(connection, wallet!, poll_pda, await getProposalKey(), governance, programId, pollId, proposal!.instructions);
+        await loadProposal();
+    }
+
+    const fundTreasury = async () => {
+        const wallet = getProvider();
+        const [treasury] = await getPda(programId, getTreasurySeedArray(creator));
+        const mint = poll!.spendMint;
+        const token = mint.equals(PublicKey.default) ? undefined : {
+            tokenAccount: (await getVoterTokenAccount(connection, wallet!.publicKey!, mint))!,
+            treasuryTokenAccount: (await getPda(programId, getTreasuryTokenSeedArray(creator, mint)))[0]
+        };
+        await fundTreasuryTransaction(connection, wallet!, treasury, programId, creator, mint, new BN(fundAmount), token);
+    }
+
+    const executeSpend = async () => {
+        const wallet = getProvider();
+        const [poll_pda, bump] = await getPda(programId, getPollSeedArray(creator, pollId));
+        const [treasury] = await getPda(programId, getTreasurySeedArray(creator));
+        if (poll!.spendMint.equals(PublicKey.default)) {
+            await executeSpendTransaction(connection, wallet!, poll_pda, treasury, poll!.spendRecipient, programId, pollId);
+            return;
+        }
+        //the recipient needs a token account of the mint, it may be empty
+        const accounts = await connection.getParsedTokenAccountsByOwner(poll!.spendRecipient, { mint: poll!.spendMint });
+        const [treasuryToken] = await getPda(programId, getTreasuryTokenSeedArray(creator, poll!.spendMint));
+        await executeSpendTransaction(connection, wallet!, poll_pda, treasury, accounts.value[0].pubkey, programId, pollId, treasuryToken);
+    }
+
+    const vetoPoll = async () => {
+        const wallet = getProvider();
+        const [poll_pda, bump] = await getPda(programId, getPollSeedArray(creator, pollId));
+        await vetoPollTransaction(connection, wallet!, poll_pda, programId, pollId);
+    }
+
+    const cancelProposal = async () => {
+        const wallet = getProvider();
+        const [poll_pda, bump] = await getPda(programId, getPollSeedArray(creator, pollId));
+        await cancelProposalTransaction(connection, wallet!, poll_pda, programId, pollId);
+    }
+
     const loadUserVote = async () => {
         const wallet = getProvider();
-        const [poll_pda, bump] = await getPda(programId, [Buffer.from(poll_seed), new Uint8Array([pollId])]);
-        const seeds: Uint8Array[] = [Buffer.from(poll_seed), new Uint8Array([pollId, bump]),
-        wallet!.publicKey!.toBuffer()];
+        const [poll_pda, bump] = await getPda(programId, getPollSeedArray(creator, pollId));
+        const seeds: Uint8Array[] = [...getPollSeedArray(creator, pollId), new Uint8Array([bump]),
+        getVoterSeed()];
         const voterPdaAccount = await getPdaAccount(connection, programId, seeds);
         const acc = getVoterPollAccount(voterPdaAccount);
         if (acc !== null)
@@ -31,11 +141,227 @@ const View = ({ connection, programId, pollId }: { connection: Connection, progr
 
     const voteOption = async (optionId: number) => {
         const wallet = getProvider();
-        const [poll_pda, bump] = await getPda(programId, [Buffer.from(poll_seed), new Uint8Array([pollId])]);
-        const seeds: Uint8Array[] = [Buffer.from(poll_seed), new Uint8Array([pollId, bump]),
-        wallet!.publicKey!.toBuffer()];
+        const [poll_pda, bump] = await getPda(programId, getPollSeedArray(creator, pollId));
+        const seeds: Uint8Array[] = [...getPollSeedArray(creator, pollId), new Uint8Array([bump]),
+        getVoterSeed()];
+        const [voterPdaAccount, vBump] = await getPda(programId, seeds);
+        if (isCommitReveal()) {
+            const salt = Buffer.from(crypto.getRandomValues(new Uint8Array(32)));
+            localStorage.setItem(getBallotKey(), JSON.stringify({ optionId, salt: salt.toString("hex") }));
+            await commitVoteTransaction(connection, wallet!, poll_pda, voterPdaAccount, programId, pollId, await getVoteCommitment(optionId, salt), await getTokenKeys(), await getProof());
+            return;
+        }
+        await voteTransaction(connection, wallet!, poll_pda, voterPdaAccount, programId, pollId, optionId, await getTokenKeys(), await getProof());
+    }
+
+    //abstain and none of the above are voted like options of any but ranked polls
+    const builtinChoice = (choice: number, title: string, votes: BN) => {
+        return (<li className="padding10" key={choice}> {title} / {votes.toString()} vote(s)
+            {(!userVote || (poll!.allowRevote && userVote.optionSelected !== choice)) && poll!.status === PollStatus.Open && <button onClick={() => { voteOption(choice) }}>Vote</button>}
+            {userVote && userVote.optionSelected === choice && <span>. You voted {title}!</span>}
+            {userVote && userVote.optionSelected === choice && poll!.status === PollStatus.Open && <button onClick={() => { retractVote() }}>Retract</button>}
+        </li>);
+    }
+
+    const isCommitReveal = () => !!poll && poll.revealEndTs > 0;
+
+    //option id and salt of a committed ballot are kept in the browser until revealed
+    const getBallotKey = () => "ballot-" + creator.toBase58() + "-" + pollId + "-" + getVoterSeed().toString("hex");
+
+    const getCommittedBallot = (): { optionId: number, salt: string } | null => {
+        const ballot = localStorage.getItem(getBallotKey());
+        return ballot ? JSON.parse(ballot) : null;
+    }
+
+    const isRevealOpen = () => isCommitReveal() && poll!.status === PollStatus.Open && poll!.endTs * 1000 <= Date.now() && Date.now() < poll!.revealEndTs * 1000;
+
+    const isUnrevealed = () => !!userVote && userVote.commitment.some(byte => byte !== 0);
+
+    const revealVote = async () => {
+        const ballot = getCommittedBallot();
+        if (!ballot)
+            return;
+        const wallet = getProvider();
+        const [poll_pda, bump] = await getPda(programId, getPollSeedArray(creator, pollId));
+        const seeds: Uint8Array[] = [...getPollSeedArray(creator, pollId), new Uint8Array([bump]),
+        getVoterSeed()];
+        const [voterPdaAccount, vBump] = await getPda(programId, seeds);
+        await revealVoteTransaction(connection, wallet!, poll_pda, voterPdaAccount, programId, pollId, ballot.optionId, Buffer.from(ballot.salt, "hex"), await getNftAccount());
+        localStorage.removeItem(getBallotKey());
+    }
+
+    const approvalVote = async () => {
+        const wallet = getProvider();
+        const [poll_pda, bump] = await getPda(programId, getPollSeedArray(creator, pollId));
+        const seeds: Uint8Array[] = [...getPollSeedArray(creator, pollId), new Uint8Array([bump]),
+        getVoterSeed()];
+        const [voterPdaAccount, vBump] = await getPda(programId, seeds);
+        await approvalVoteTransaction(connection, wallet!, poll_pda, voterPdaAccount, programId, pollId, selections, await getTokenKeys(), await getProof());
+        setSelections(new BN(0));
+    }
+
+    const quadraticVote = async () => {
+        const wallet = getProvider();
+        const [poll_pda, bump] = await getPda(programId, getPollSeedArray(creator, pollId));
+        const seeds: Uint8Array[] = [...getPollSeedArray(creator, pollId), new Uint8Array([bump]),
+        getVoterSeed()];
+        const [voterPdaAccount, vBump] = await getPda(programId, seeds);
+        await quadraticVoteTransaction(connection, wallet!, poll_pda, voterPdaAccount, programId, pollId, allocation, await getTokenKeys(), await getProof());
+        setAllocation([]);
+    }
+
+    const setOptionVotes = (optionIdx: number, votes: number) => {
+        const next = [...allocation];
+        while (next.length < poll!.optionsLength)
+            next.push(0);
+        next[optionIdx] = Math.max(0, votes);
+        setAllocation(next);
+    }
+
+    //k votes on an option cost k² voice credits
+    const creditsSpent = allocation.reduce((sum, votes) => sum + votes * votes, 0);
+
+    const isAllowlisted = () => !!poll && poll.allowlistRoot.some(byte => byte !== 0);
+
+    //merkle proof of the voter on allowlisted polls, voters that already voted need none
+    const getProof = async () => {
+        if (!isAllowlisted() || userVote)
+            return [];
+        const members = allowlist.split(/\s+/).filter(key => key.length > 0).map(key => new PublicKey(key));
+        return (await getAllowlistProof(members, getProvider()!.publicKey!)) || [];
+    }
+
+    //voter's token account of the vote mint, undefined for polls without token weights
+    const getTokenAccount = async () => {
+        if (!poll || poll.voteMint.equals(PublicKey.default))
+            return undefined;
+        return await getVoterTokenAccount(connection, getProvider()!.publicKey!, poll.voteMint);
+    }
+
+    const getEscrow = async () => {
+        const [poll_pda, bump] = await getPda(programId, getPollSeedArray(creator, pollId));
+        return (await getPda(programId, getEscrowSeedArray(creator, pollId, bump, getProvider()!.publicKey!)))[0];
+    }
+
+    //delegation accounts of the delegators, their per poll delegation is used when it exists
+    const getDelegationKeys = async () => {
+        const [poll_pda, bump] = await getPda(programId, getPollSeedArray(creator, pollId));
+        const keys: AccountMeta[] = [];
+        for (const key of delegators.split(/\s+/).filter(key => key.length > 0)) {
+            const delegator = new PublicKey(key);
+            const [pollDelegation] = await getPda(programId, getDelegationSeedArray(delegator, poll_pda));
+            const [delegation] = await getPda(programId, getDelegationSeedArray(delegator));
+            const [voterPdaAccount] = await getPda(programId, [...getPollSeedArray(creator, pollId), new Uint8Array([bump]), delegator.toBuffer()]);
+            const tokenAccount = poll!.voteMint.equals(PublicKey.default) ? undefined : await getVoterTokenAccount(connection, delegator, poll!.voteMint);
+            if (await connection.getAccountInfo(pollDelegation))
+                keys.push(...getVoteDelegationKeys(pollDelegation, voterPdaAccount, undefined, tokenAccount));
+            else
+                keys.push(...getVoteDelegationKeys(delegation, voterPdaAccount, pollDelegation, tokenAccount));
+        }
+        return keys;
+    }
+
+    //accounts a vote on this poll needs
+    const getTokenKeys = async () => [...await getVoterTokenKeys(), ...await getDelegationKeys()];
+
+    const getVoterTokenKeys = async () => {
+        const mint = getNftMint();
+        if (mint)
+            return getVoteNftKeys((await getNftAccount())!, (await getPda(TOKEN_METADATA_PROGRAM_ID, getNftMetadataSeedArray(mint)))[0]);
+        const tokenAccount = await getTokenAccount();
+        if (!tokenAccount || !poll!.lockTokens)
+            return getVoteTokenKeys(tokenAccount);
+        return getVoteTokenKeys(tokenAccount, { mint: poll!.voteMint, escrow: await getEscrow() });
+    }
+
+    const withdraw = async () => {
+        const wallet = getProvider();
+        const [poll_pda, bump] = await getPda(programId, getPollSeedArray(creator, pollId));
+        const tokenAccount = await getTokenAccount();
+        if (!tokenAccount)
+            return;
+        await withdrawTransaction(connection, wallet!, poll_pda, await getEscrow(), tokenAccount, creator, programId, pollId);
+    }
+
+    const delegateVote = async () => {
+        const wallet = getProvider();
+        const [poll_pda, bump] = await getPda(programId, getPollSeedArray(creator, pollId));
+        const [delegation] = await getPda(programId, getDelegationSeedArray(wallet!.publicKey!, poll_pda));
+        await delegateTransaction(connection, wallet!, delegation, new PublicKey(delegate), programId, poll_pda);
+    }
+
+    const undelegateVote = async () => {
+        const wallet = getProvider();
+        const [poll_pda, bump] = await getPda(programId, getPollSeedArray(creator, pollId));
+        const [delegation] = await getPda(programId, getDelegationSeedArray(wallet!.publicKey!, poll_pda));
+        await undelegateTransaction(connection, wallet!, delegation, programId, poll_pda);
+    }
+
+    //ballot box of a ranked choice poll, undefined for other polls
+    const getBallotBox = async () => {
+        if (poll?.voteMode !== VoteMode.Ranked)
+            return undefined;
+        const [poll_pda, bump] = await getPda(programId, getPollSeedArray(creator, pollId));
+        return (await getPda(programId, getBallotBoxSeedArray(creator, pollId, bump)))[0];
+    }
+
+    const rankedVote = async () => {
+        const wallet = getProvider();
+        const [poll_pda, bump] = await getPda(programId, getPollSeedArray(creator, pollId));
+        const seeds: Uint8Array[] = [...getPollSeedArray(creator, pollId), new Uint8Array([bump]),
+        getVoterSeed()];
         const [voterPdaAccount, vBump] = await getPda(programId, seeds);
-        await voteTransaction(connection, wallet!, poll_pda, voterPdaAccount, programId, pollId, optionId);
+        await rankedVoteTransaction(connection, wallet!, poll_pda, voterPdaAccount, (await getBallotBox())!, programId, pollId, ranking, await getTokenKeys(), await getProof());
+        setRanking([]);
+    }
+
+    const tallyRound = async () => {
+        const wallet = getProvider();
+        const [poll_pda, bump] = await getPda(programId, getPollSeedArray(creator, pollId));
+        await tallyRoundTransaction(connection, wallet!, poll_pda, (await getBallotBox())!, programId, pollId);
+    }
+
+    const rankOption = (optionId: number) => {
+        setRanking(ranking.includes(optionId) ? ranking.filter(id => id !== optionId) : [...ranking, optionId]);
+    }
+
+    const toggleSelection = (optionIdx: number) => {
+        setSelections(selections.clone().setn(optionIdx, !selections.testn(optionIdx)));
+    }
+
+    const closePoll = async () => {
+        const wallet = getProvider();
+        const [poll_pda, bump] = await getPda(programId, getPollSeedArray(creator, pollId));
+        await closePollTransaction(connection, wallet!, poll_pda, programId, pollId);
+    }
+
+    //the voter pda's rent goes back to whoever paid it, the voter or their delegate
+    const getRentPayer = () => userVote && !userVote.payer.equals(PublicKey.default) ? userVote.payer : getProvider()!.publicKey!;
+
+    const closeVoterAccount = async () => {
+        const wallet = getProvider();
+        const [poll_pda, bump] = await getPda(programId, getPollSeedArray(creator, pollId));
+        const seeds: Uint8Array[] = [...getPollSeedArray(creator, pollId), new Uint8Array([bump]),
+        getVoterSeed()];
+        const [voterPdaAccount, vBump] = await getPda(programId, seeds);
+        await closeVoterAccountTransaction(connection, wallet!, poll_pda, voterPdaAccount, getRentPayer(), creator, programId, pollId, await getNftAccount());
+        setUserVote(null);
+    }
+
+    const retractVote = async () => {
+        const wallet = getProvider();
+        const [poll_pda, bump] = await getPda(programId, getPollSeedArray(creator, pollId));
+        const seeds: Uint8Array[] = [...getPollSeedArray(creator, pollId), new Uint8Array([bump]),
+        getVoterSeed()];
+        const [voterPdaAccount, vBump] = await getPda(programId, seeds);
+        await retractVoteTransaction(connection, wallet!, poll_pda, voterPdaAccount, getRentPayer(), programId, pollId, await getBallotBox(), await getNftAccount());
+        setUserVote(null);
+    }
+
+    const closePollAccount = async () => {
+        const wallet = getProvider();
+        const [poll_pda, bump] = await getPda(programId, getPollSeedArray(creator, pollId));
+        await closePollAccountTransaction(connection, wallet!, poll_pda, programId, pollId, await getBallotBox());
     }
 
     const pollAccountChangeCallback = async () => {
@@ -51,9 +377,9 @@ const View = ({ connection, programId, pollId }: { connection: Connection, progr
 
     const initCallback = async () => {
         const wallet = getProvider();
-        const [poll_pda, bump] = await getPda(programId, [Buffer.from(poll_seed), new Uint8Array([pollId])]);
-        const seeds: Uint8Array[] = [Buffer.from(poll_seed), new Uint8Array([pollId, bump]),
-        wallet!.publicKey!.toBuffer()];
+        const [poll_pda, bump] = await getPda(programId, getPollSeedArray(creator, pollId));
+        const seeds: Uint8Array[] = [...getPollSeedArray(creator, pollId), new Uint8Array([bump]),
+        getVoterSeed()];
         const [voterPdaAccount, vBump] = await getPda(programId, seeds);
         setAccountUpdateCallback(voterPdaAccount, voteAccountChangeCallback, connection);
         setAccountUpdateCallback(poll_pda, pollAccountChangeCallback, connection);
@@ -62,6 +388,7 @@ const View = ({ connection, programId, pollId }: { connection: Connection, progr
     useEffect(() => {
         loadPoll();
         loadUserVote();
+        loadProposal();
         initCallback();
     }, [])
 
@@ -73,14 +400,88 @@ const View = ({ connection, programId, pollId }: { connection: Connection, progr
                     <div>
                         Proposal Title: {poll.title}
                     </div>
-                    <ul >{poll?.options.map((val) => {
+                    {poll.startTs > 0 && <div>Voting starts: {new Date(poll.startTs * 1000).toLocaleString()}</div>}
+                    {poll.endTs > 0 && <div>Voting ends: {new Date(poll.endTs * 1000).toLocaleString()}</div>}
+                    {poll.status === PollStatus.Finalized && <div>Poll closed. {(poll.winner > 0 && ("Winner: (" + poll.winner + ") " + poll.options[poll.winner - 1].title)) || "Result: tie"}</div>}
+                    {(poll.quorum.gtn(0) || poll.threshold > 0 || poll.passOption > 0) && <div>Passes with {poll.quorum.toString()} vote(s) cast and more than {poll.threshold / 100}% of the votes for {poll.passOption > 0 ? "option " + poll.passOption : "the winner"}, {poll.votesCast.toString()} vote(s) cast so far</div>}
+                    {poll.outcome !== PollOutcome.Undecided && <div>Outcome: {PollOutcome[poll.outcome]}</div>}
+                    {poll.vetoThreshold > 0 && <div>Can be vetoed by {poll.vetoThreshold} of {poll.vetoKeys.map(key => key.toBase58()).join(", ")}</div>}
+                    {poll.vetoThreshold === 1 && poll.outcome === PollOutcome.Passed && poll.proposalState !== ProposalState.Executed && poll.vetoKeys.some(key => key.equals(getProvider()!.publicKey!)) && <button onClick={() => { vetoPoll() }}>Veto poll</button>}
+                    {proposal && <div>Executable proposal with {proposal.instructions.length} instruction(s){proposal.instructions.map((instruction, i) => <div key={i}>({i + 1}) program {instruction.programId.toBase58()}, {instruction.keys.length} account(s), data {instruction.data.toString("hex")}</div>)}</div>}
+                    {poll.proposalState !== ProposalState.None && <div>Proposal: {ProposalState[poll.proposalState]}{poll.proposalState === ProposalState.Queued && ", executable from " + new Date(poll.executeAfter * 1000).toLocaleString()}</div>}
+                    {proposal && poll.proposalState === ProposalState.Queued && poll.executeAfter * 1000 <= Date.now() && <button onClick={() => { executeProposal() }}>Execute proposal</button>}
+                    {poll.spendAmount.gtn(0) && <div>Spends {poll.spendAmount.toString()} {poll.spendMint.equals(PublicKey.default) ? "lamport(s)" : "token(s) of " + poll.spendMint.toBase58()} to {poll.spendRecipient.toBase58()} from the treasury
+                        <input type="number" min={0} value={fundAmount} onChange={(e) => { setFundAmount(e.target.value) }} placeholder="Amount to deposit" />
+                        <button onClick={() => { fundTreasury() }}>Fund treasury</button>
+                    </div>}
+                    {poll.spendAmount.gtn(0) && poll.proposalState === ProposalState.Queued && poll.executeAfter * 1000 <= Date.now() && <button onClick={() => { executeSpend() }}>Execute spend</button>}
+                    {poll.proposalState === ProposalState.Queued && poll.executeAfter * 1000 > Date.now() && (poll.creator.equals(getProvider()!.publicKey!) || poll.council.equals(getProvider()!.publicKey!)) && <button onClick={() => { cancelProposal() }}>Cancel proposal</button>}
+                    {poll.status === PollStatus.Open && poll.startTs * 1000 > Date.now() && poll.creator.equals(getProvider()!.publicKey!) && <div>
+                        <input type="text" value={instructionProgram} onChange={(e) => { setInstructionProgram(e.target.value) }} placeholder="Program id of the instruction" />
+                        <textarea value={instructionAccounts} onChange={(e) => { setInstructionAccounts(e.target.value) }} placeholder="Accounts, one per line: pubkey [s][w]" />
+                        <input type="text" value={instructionData} onChange={(e) => { setInstructionData(e.target.value) }} placeholder="Instruction data (hex)" />
+                        <button onClick={() => { addProposalInstruction() }}>Add instruction</button>
+                    </div>}
+                    {poll.status === PollStatus.Open && poll.creator.equals(getProvider()!.publicKey!) && <button onClick={() => { closePoll() }}>Close Poll</button>}
+                    {poll.status === PollStatus.Finalized && poll.creator.equals(getProvider()!.publicKey!) && <button onClick={() => { closePollAccount() }}>Delete Poll</button>}
+                    {poll.status === PollStatus.Finalized && userVote && <button onClick={() => { closeVoterAccount() }}>Reclaim Vote Rent</button>}
+                    {!poll.voteMint.equals(PublicKey.default) && <div>Votes are weighted by token {poll.voteMint.toBase58()}{userVote && (", your weight: " + userVote.weight.toString())}{poll.lockTokens && ", voted tokens are locked until voting is over"}</div>}
+                    {poll.lockTokens && (poll.status === PollStatus.Finalized || (poll.endTs > 0 && poll.endTs * 1000 <= Date.now())) && <button onClick={() => { withdraw() }}>Withdraw tokens</button>}
+                    {isNftGated() && <div>Only holders of an nft of collection {poll.nftCollection.toBase58()} can vote, each nft once. <input type="text" value={nftMint} onChange={(e) => { setNftMint(e.target.value) }} onBlur={() => { loadUserVote() }} placeholder="Mint of your nft" /></div>}
+                    {isAllowlisted() && <div>Only allowlisted voters can vote{!userVote && poll.status === PollStatus.Open && <textarea value={allowlist} onChange={(e) => { setAllowlist(e.target.value) }} placeholder="Allowlist of the poll, one pubkey per line" />}</div>}
+                    {!isAllowlisted() && !isNftGated() && !poll.lockTokens && !isCommitReveal() && poll.status === PollStatus.Open && <div>
+                        <input type="text" value={delegate} onChange={(e) => { setDelegate(e.target.value) }} placeholder="Delegate your vote to" />
+                        <button onClick={() => { delegateVote() }}>Delegate</button>
+                        <button onClick={() => { undelegateVote() }}>Undelegate</button>
+                        <textarea value={delegators} onChange={(e) => { setDelegators(e.target.value) }} placeholder="Wallets that delegated to you, one pubkey per line" />
+                    </div>}
+                    {isCommitReveal() && <div>Secret ballots, votes are counted once revealed between {new Date(poll.endTs * 1000).toLocaleString()} and {new Date(poll.revealEndTs * 1000).toLocaleString()}. Ballots not revealed: {poll.unrevealed}{isUnrevealed() && ", your ballot is committed" + (getCommittedBallot() ? " (option " + getCommittedBallot()!.optionId + ")" : "")}</div>}
+                    {isRevealOpen() && isUnrevealed() && getCommittedBallot() && <button onClick={() => { revealVote() }}>Reveal vote</button>}
+                    {poll.voteMode === VoteMode.Approval && <div>Approval voting{poll.maxSelections > 0 && (", select up to " + poll.maxSelections + " option(s)")}</div>}
+                    {poll.voteMode === VoteMode.Approval && userVote && poll.status === PollStatus.Open && <button onClick={() => { retractVote() }}>Retract</button>}
+                    {poll.voteMode === VoteMode.Approval && (!userVote || poll.allowRevote) && poll.status === PollStatus.Open && <button onClick={() => { approvalVote() }}>Vote for selected</button>}
+                    {poll.voteMode === VoteMode.Ranked && <div>Ranked choice voting, votes show first choices. Your ranking: {ranking.join(", ")}</div>}
+                    {poll.voteMode === VoteMode.Ranked && userVote && poll.status === PollStatus.Open && <button onClick={() => { retractVote() }}>Retract</button>}
+                    {poll.voteMode === VoteMode.Ranked && (!userVote || poll.allowRevote) && poll.status === PollStatus.Open && ranking.length > 0 && <button onClick={() => { rankedVote() }}>Submit ranking</button>}
+                    {poll.voteMode === VoteMode.Ranked && (poll.status === PollStatus.Finalized || (poll.endTs > 0 && poll.endTs * 1000 <= Date.now())) && poll.winner === 0 && <button onClick={() => { tallyRound() }}>Run tally round</button>}
+                    {poll.voteMode === VoteMode.Quadratic && <div>Quadratic voting, k votes cost k² of your {poll.credits.toString()} voice credits. Credits spent: {creditsSpent}</div>}
+                    {poll.voteMode === VoteMode.Quadratic && userVote && poll.status === PollStatus.Open && <button onClick={() => { retractVote() }}>Retract</button>}
+                    {poll.voteMode === VoteMode.Quadratic && (!userVote || poll.allowRevote) && poll.status === PollStatus.Open && creditsSpent > 0 && <button onClick={() => { quadraticVote() }}>Spend credits</button>}
+                    <ul >{poll.voteMode === VoteMode.Quadratic && poll?.options.map((val) => {
+                        return (<>
+                            <li className="padding10" key={val.id}> ({val.id + 1}) {val.title} / {val.votes} vote(s)
+                                {(!userVote || poll.allowRevote) && poll.status === PollStatus.Open && <input type="number" min={0} value={allocation[val.id] || 0} onChange={(e) => { setOptionVotes(val.id, parseInt(e.target.value) || 0) }} />}
+                                {userVote && (userVote.allocation[val.id] || 0) > 0 && <span>. You gave this option {userVote.allocation[val.id]} vote(s)!</span>}
+                            </li>
+                        </>)
+                    })}
+                    {poll.voteMode === VoteMode.Ranked && poll?.options.map((val) => {
+                        return (<>
+                            <li className="padding10" key={val.id}> ({val.id + 1}) {val.title} / {val.votes} first choice vote(s)
+                                {(!userVote || poll.allowRevote) && poll.status === PollStatus.Open && <button onClick={() => { rankOption(val.id + 1) }}>{ranking.includes(val.id + 1) ? "Unrank" : "Rank next"}</button>}
+                                {userVote && userVote.optionSelected === (val.id + 1) && <span>. Your first choice!</span>}
+                            </li>
+                        </>)
+                    })}
+                    {poll.voteMode === VoteMode.Approval && poll?.options.map((val) => {
+                        return (<>
+                            <li className="padding10" key={val.id}> ({val.id + 1}) {val.title} / {val.votes} vote(s)
+                                {(!userVote || poll.allowRevote) && poll.status === PollStatus.Open && <input type="checkbox" checked={selections.testn(val.id)} onChange={() => { toggleSelection(val.id) }} />}
+                                {userVote && userVote.selections.testn(val.id) && <span>. You voted for this option!</span>}
+                            </li>
+                        </>)
+                    })}
+                    {poll.voteMode === VoteMode.Single && poll?.options.map((val) => {
                         return (<>
                             <li className="padding10" key={val.id}> ({val.id + 1}) {val.title} / {val.votes} vote(s)
-                                {!userVote && <button onClick={() => { voteOption(val.id + 1) }}>Vote</button>}
+                                {(!userVote || (poll.allowRevote && userVote.optionSelected !== (val.id + 1))) && poll.status === PollStatus.Open && <button onClick={() => { voteOption(val.id + 1) }}>Vote</button>}
                                 {userVote && userVote.optionSelected === (val.id + 1) && <span>. You voted for this option!</span>}
+                                {userVote && userVote.optionSelected === (val.id + 1) && poll.status === PollStatus.Open && <button onClick={() => { retractVote() }}>Retract</button>}
                             </li>
                         </>)
                     })}
+                    {poll.allowAbstain && builtinChoice(ABSTAIN_OPTION_ID, "Abstain", poll.abstainVotes)}
+                    {poll.allowNota && builtinChoice(NOTA_OPTION_ID, "None of the above", poll.notaVotes)}
                     </ul>
                 </>
             )